@@ -0,0 +1,27 @@
+use syn::{Attribute, Field, Lit, MetaItem};
+
+/// Gets the value of a `#[name = "value"]` attribute
+pub fn string_value(attrs: &[Attribute], name: &str) -> Option<String> {
+    attrs.iter().filter_map(|attr| match attr.value {
+        MetaItem::NameValue(ref ident, Lit::Str(ref value, _)) if ident.as_ref() == name => {
+            Some(value.clone())
+        },
+        _ => None,
+    }).next()
+}
+
+/// Checks whether the field has a `#[name]` attribute
+pub fn has_word(field: &Field, name: &str) -> bool {
+    field.attrs.iter().any(|attr| match attr.value {
+        MetaItem::Word(ref ident) => ident.as_ref() == name,
+        _ => false,
+    })
+}
+
+/// Returns the named fields of a struct, panicking with a helpful message otherwise
+pub fn struct_fields<'a>(ast: &'a ::syn::DeriveInput, derive: &str) -> &'a [Field] {
+    match ast.body {
+        ::syn::Body::Struct(::syn::VariantData::Struct(ref fields)) => fields,
+        _ => panic!("#[derive({})] is only supported on structs with named fields", derive),
+    }
+}
@@ -0,0 +1,81 @@
+//! Derives for the traits in `rusted_cypher::ogm`
+//!
+//! The derives are re-exported by `rusted_cypher::ogm` with its `derive` feature, on by default,
+//! so this crate does not need to be a dependency of its own.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate rusted_cypher;
+//! use rusted_cypher::ogm::GraphNode;
+//!
+//! #[derive(GraphNode)]
+//! #[label = "Language"]
+//! struct Language {
+//!     #[key]
+//!     name: String,
+//!     level: String,
+//!     safe: bool,
+//! }
+//!
+//! impl_decodable!(Language { name, level, safe });
+//! # fn main() {
+//! # assert_eq!("Language", Language::label());
+//! # }
+//! ```
+//!
+//! The label defaults to the name of the struct. At least one field must be marked with `#[key]`.
+//!
+//! ```
+//! # #[macro_use] extern crate rusted_cypher;
+//! # use rusted_cypher::ogm::GraphNode;
+//! use rusted_cypher::ogm::GraphRelationship;
+//!
+//! # #[derive(GraphNode)]
+//! # struct Language {
+//! #     #[key]
+//! #     name: String,
+//! # }
+//! # impl_decodable!(Language { name });
+//! #[derive(GraphRelationship)]
+//! #[start = "Language"]
+//! #[end = "Language"]
+//! struct InfluencedBy {
+//!     strength: i32,
+//! }
+//!
+//! impl_decodable!(InfluencedBy { strength });
+//! # fn main() {
+//! # assert_eq!("INFLUENCED_BY", InfluencedBy::rel_type());
+//! # }
+//! ```
+//!
+//! The relationship type defaults to the name of the struct in upper snake case, `INFLUENCED_BY`
+//! in the example above, and can be set with `#[rel_type = "..."]`.
+
+extern crate proc_macro;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+mod attr;
+mod node;
+mod relationship;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(GraphNode, attributes(label, key))]
+pub fn derive_graph_node(input: TokenStream) -> TokenStream {
+    let source = input.to_string();
+    let ast = syn::parse_derive_input(&source).unwrap();
+    let expanded = node::expand(&ast);
+    expanded.parse().unwrap()
+}
+
+#[proc_macro_derive(GraphRelationship, attributes(rel_type, start, end))]
+pub fn derive_graph_relationship(input: TokenStream) -> TokenStream {
+    let source = input.to_string();
+    let ast = syn::parse_derive_input(&source).unwrap();
+    let expanded = relationship::expand(&ast);
+    expanded.parse().unwrap()
+}
@@ -0,0 +1,56 @@
+use quote::Tokens;
+use syn::{DeriveInput, Ident};
+
+use attr;
+
+pub fn expand(ast: &DeriveInput) -> Tokens {
+    let name = &ast.ident;
+    let label = attr::string_value(&ast.attrs, "label").unwrap_or(name.as_ref().to_owned());
+    let fields = attr::struct_fields(ast, "GraphNode");
+
+    let idents: Vec<&Ident> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let names: Vec<String> = idents.iter().map(|ident| ident.as_ref().to_owned()).collect();
+
+    let key_idents: Vec<&Ident> = fields.iter()
+        .filter(|field| attr::has_word(field, "key"))
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect();
+    let key_names: Vec<String> = key_idents.iter().map(|ident| ident.as_ref().to_owned()).collect();
+
+    if key_idents.is_empty() {
+        panic!("#[derive(GraphNode)] requires at least one field marked with #[key]");
+    }
+
+    let key_names_ref = &key_names;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::rusted_cypher::ogm::GraphNode for #name #ty_generics #where_clause {
+            fn label() -> &'static str {
+                #label
+            }
+
+            fn key_properties() -> Vec<&'static str> {
+                vec![#(#key_names_ref),*]
+            }
+
+            fn key(&self) -> ::rusted_cypher::ogm::Properties {
+                let mut key = ::rusted_cypher::ogm::Properties::new();
+                #(
+                    key.insert(#key_names.to_owned(),
+                               ::rusted_cypher::ogm::ToJson::to_json(&self.#key_idents));
+                )*
+                key
+            }
+
+            fn properties(&self) -> ::rusted_cypher::ogm::Properties {
+                let mut properties = ::rusted_cypher::ogm::Properties::new();
+                #(
+                    properties.insert(#names.to_owned(),
+                                      ::rusted_cypher::ogm::ToJson::to_json(&self.#idents));
+                )*
+                properties
+            }
+        }
+    }
+}
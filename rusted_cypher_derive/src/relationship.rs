@@ -0,0 +1,69 @@
+use quote::Tokens;
+use syn::{self, DeriveInput, Ident};
+
+use attr;
+
+/// Converts a struct name such as `WorksAt` into a relationship type such as `WORKS_AT`
+fn default_rel_type(name: &str) -> String {
+    let mut rel_type = String::with_capacity(name.len() + 4);
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            rel_type.push('_');
+        }
+        rel_type.extend(c.to_uppercase());
+    }
+    rel_type
+}
+
+fn node_type(ast: &DeriveInput, name: &str) -> syn::Ty {
+    let value = attr::string_value(&ast.attrs, name).unwrap_or_else(|| {
+        panic!("#[derive(GraphRelationship)] requires the #[{} = \"...\"] attribute", name)
+    });
+
+    syn::parse_type(&value).unwrap_or_else(|e| {
+        panic!("Invalid type in #[{} = \"{}\"]: {}", name, value, e)
+    })
+}
+
+pub fn expand(ast: &DeriveInput) -> Tokens {
+    let name = &ast.ident;
+    let rel_type = attr::string_value(&ast.attrs, "rel_type")
+        .unwrap_or(default_rel_type(name.as_ref()));
+    let start = node_type(ast, "start");
+    let end = node_type(ast, "end");
+    let fields = attr::struct_fields(ast, "GraphRelationship");
+
+    let idents: Vec<&Ident> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let names: Vec<String> = idents.iter().map(|ident| ident.as_ref().to_owned()).collect();
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::rusted_cypher::ogm::GraphRelationship for #name #ty_generics #where_clause {
+            type Start = #start;
+            type End = #end;
+
+            fn rel_type() -> &'static str {
+                #rel_type
+            }
+
+            fn properties(&self) -> ::rusted_cypher::ogm::Properties {
+                let mut properties = ::rusted_cypher::ogm::Properties::new();
+                #(
+                    properties.insert(#names.to_owned(),
+                                      ::rusted_cypher::ogm::ToJson::to_json(&self.#idents));
+                )*
+                properties
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn default_rel_type() {
+        assert_eq!("KNOWS", super::default_rel_type("Knows"));
+        assert_eq!("WORKS_AT", super::default_rel_type("WorksAt"));
+    }
+}
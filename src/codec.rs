@@ -0,0 +1,119 @@
+//! Implementations of `Decodable` and `Encodable` for structs with named fields
+//!
+//! Current compilers no longer provide `#[derive(RustcDecodable, RustcEncodable)]`. The
+//! `impl_decodable!` and `impl_encodable!` macros implement the traits the way the derives did,
+//! taking the name of the struct, its type parameters and its fields:
+//!
+//! ```
+//! #[macro_use] extern crate rusted_cypher;
+//! extern crate rustc_serialize;
+//!
+//! use rustc_serialize::json;
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Language {
+//!     name: String,
+//!     level: String,
+//! }
+//!
+//! impl_decodable!(Language { name, level });
+//! impl_encodable!(Language { name, level });
+//!
+//! # fn main() {
+//! let rust = Language { name: "Rust".to_owned(), level: "low".to_owned() };
+//! let encoded = json::encode(&rust).unwrap();
+//! assert_eq!(r#"{"name":"Rust","level":"low"}"#, encoded);
+//! assert_eq!(rust, json::decode(&encoded).unwrap());
+//! # }
+//! ```
+//!
+//! Type parameters are bound to `Decodable` or `Encodable`, as in
+//! `impl_decodable!(Row<T> { value })`, and `impl_encodable!` also takes a single lifetime, as in
+//! `impl_encodable!(Statements<'a> { statements })`.
+
+#[doc(hidden)]
+pub use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __decode_struct {
+    ( $decoder:expr, $name:ident { $( $field:ident ),* } ) => {{
+        const FIELDS: &'static [&'static str] = &[ $( stringify!($field) ),* ];
+        $decoder.read_struct(stringify!($name), FIELDS.len(), |d| {
+            Ok($name {
+                $( $field: d.read_struct_field(
+                    stringify!($field),
+                    FIELDS.iter().position(|field| *field == stringify!($field)).unwrap_or(0),
+                    $crate::codec::Decodable::decode)?, )*
+            })
+        })
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __encode_struct {
+    ( $value:expr, $encoder:expr, $name:ident { $( $field:ident ),* } ) => {{
+        const FIELDS: &'static [&'static str] = &[ $( stringify!($field) ),* ];
+        $encoder.emit_struct(stringify!($name), FIELDS.len(), |s| {
+            $( s.emit_struct_field(
+                stringify!($field),
+                FIELDS.iter().position(|field| *field == stringify!($field)).unwrap_or(0),
+                |s| $crate::codec::Encodable::encode(&$value.$field, s))?; )*
+            Ok(())
+        })
+    }};
+}
+
+/// Implements `rustc_serialize::Decodable` for a struct with named fields
+///
+/// See the [module documentation](codec/index.html).
+#[macro_export]
+macro_rules! impl_decodable {
+    ( $name:ident { $( $field:ident ),* $(,)* } ) => {
+        impl $crate::codec::Decodable for $name {
+            fn decode<D: $crate::codec::Decoder>(d: &mut D) -> Result<Self, D::Error> {
+                __decode_struct!(d, $name { $( $field ),* })
+            }
+        }
+    };
+    ( $name:ident < $( $param:ident ),+ > { $( $field:ident ),* $(,)* } ) => {
+        impl<$( $param: $crate::codec::Decodable ),+> $crate::codec::Decodable
+            for $name<$( $param ),+>
+        {
+            fn decode<D: $crate::codec::Decoder>(d: &mut D) -> Result<Self, D::Error> {
+                __decode_struct!(d, $name { $( $field ),* })
+            }
+        }
+    };
+}
+
+/// Implements `rustc_serialize::Encodable` for a struct with named fields
+///
+/// See the [module documentation](codec/index.html).
+#[macro_export]
+macro_rules! impl_encodable {
+    ( $name:ident { $( $field:ident ),* $(,)* } ) => {
+        impl $crate::codec::Encodable for $name {
+            fn encode<S: $crate::codec::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+                __encode_struct!(self, s, $name { $( $field ),* })
+            }
+        }
+    };
+    ( $name:ident < $lifetime:lifetime > { $( $field:ident ),* $(,)* } ) => {
+        impl<$lifetime> $crate::codec::Encodable for $name<$lifetime> {
+            fn encode<S: $crate::codec::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+                __encode_struct!(self, s, $name { $( $field ),* })
+            }
+        }
+    };
+    ( $name:ident < $( $param:ident ),+ > { $( $field:ident ),* $(,)* } ) => {
+        impl<$( $param: $crate::codec::Encodable ),+> $crate::codec::Encodable
+            for $name<$( $param ),+>
+        {
+            fn encode<S: $crate::codec::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+                __encode_struct!(self, s, $name { $( $field ),* })
+            }
+        }
+    };
+}
@@ -0,0 +1,294 @@
+//! Safe interpolation of labels, relationship types and property keys
+//!
+//! Identifiers cannot be sent as parameters, so they must be part of the statement text. The
+//! types in this module are validated when created and always escaped with backticks when
+//! interpolated through `Statement::template`, while values still go through parameters.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rusted_cypher::Statement;
+//! # use rusted_cypher::cypher::identifier::{Identifier, Label};
+//! let label = Label::new("Person").unwrap();
+//! let key = Identifier::new("first name").unwrap();
+//!
+//! let statement = Statement::template("MATCH (n:#{label}) WHERE n.#{key} = {name} RETURN n")
+//!     .with_identifier("label", &label)
+//!     .with_identifier("key", &key)
+//!     .with_param("name", "Alice".to_owned())
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(statement.statement(), "MATCH (n:`Person`) WHERE n.`first name` = {name} RETURN n");
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
+use rustc_serialize::json::{Json, ToJson};
+
+use ::error::GraphError;
+use super::statement::Statement;
+
+/// Escapes an identifier, such as a label, relationship type or property key, with backticks
+///
+/// Identifiers cannot be sent as parameters, so they must be escaped before being interpolated in
+/// the statement text. Prefer the validated types of this module when the identifier comes from
+/// user input.
+///
+/// # Examples
+///
+/// ```
+/// # use rusted_cypher::cypher::escape_identifier;
+/// assert_eq!(escape_identifier("Person"), "`Person`");
+/// assert_eq!(escape_identifier("odd`name"), "`odd``name`");
+/// ```
+pub fn escape_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace("`", "``"))
+}
+
+/// Whether the name can be written in cypher without escaping, like `name` or `_id2`
+pub(crate) fn is_plain_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// A validated identifier, such as a property key or variable name
+///
+/// Identifiers must not be empty nor contain control characters. They are displayed escaped with
+/// backticks.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Identifier(String);
+
+impl Identifier {
+    pub fn new(name: &str) -> Result<Self, GraphError> {
+        if name.is_empty() {
+            return Err(GraphError::new("Identifier must not be empty"));
+        }
+
+        if name.chars().any(|c| c.is_control()) {
+            return Err(GraphError::new(&format!("Identifier {:?} contains control characters",
+                                                name)));
+        }
+
+        Ok(Identifier(name.to_owned()))
+    }
+
+    /// Returns the identifier as given, without escaping
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the identifier escaped with backticks
+    pub fn escaped(&self) -> String {
+        escape_identifier(&self.0)
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.escaped())
+    }
+}
+
+/// A validated node label
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Label(Identifier);
+
+impl Label {
+    pub fn new(name: &str) -> Result<Self, GraphError> {
+        Identifier::new(name).map(Label)
+    }
+}
+
+impl Deref for Label {
+    type Target = Identifier;
+
+    fn deref(&self) -> &Identifier {
+        &self.0
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A validated relationship type
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RelType(Identifier);
+
+impl RelType {
+    pub fn new(name: &str) -> Result<Self, GraphError> {
+        Identifier::new(name).map(RelType)
+    }
+}
+
+impl Deref for RelType {
+    type Target = Identifier;
+
+    fn deref(&self) -> &Identifier {
+        &self.0
+    }
+}
+
+impl fmt::Display for RelType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Types that can be interpolated in a `StatementTemplate`
+pub trait AsIdentifier {
+    fn as_identifier(&self) -> &Identifier;
+}
+
+impl AsIdentifier for Identifier {
+    fn as_identifier(&self) -> &Identifier {
+        self
+    }
+}
+
+impl AsIdentifier for Label {
+    fn as_identifier(&self) -> &Identifier {
+        &self.0
+    }
+}
+
+impl AsIdentifier for RelType {
+    fn as_identifier(&self) -> &Identifier {
+        &self.0
+    }
+}
+
+/// Builds a `Statement` from a text with `#{name}` placeholders for identifiers
+///
+/// Only the types of this module can be interpolated. Values are added as parameters, exactly as
+/// in `Statement`.
+#[derive(Clone, Debug)]
+pub struct StatementTemplate {
+    text: String,
+    identifiers: BTreeMap<String, Identifier>,
+    parameters: BTreeMap<String, Json>,
+}
+
+impl StatementTemplate {
+    pub fn new(text: &str) -> Self {
+        StatementTemplate {
+            text: text.to_owned(),
+            identifiers: BTreeMap::new(),
+            parameters: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the identifier for the `#{key}` placeholder in builder style
+    pub fn with_identifier<I: AsIdentifier>(mut self, key: &str, identifier: &I) -> Self {
+        self.add_identifier(key, identifier);
+        self
+    }
+
+    /// Sets the identifier for the `#{key}` placeholder
+    pub fn add_identifier<I: AsIdentifier>(&mut self, key: &str, identifier: &I) {
+        self.identifiers.insert(key.to_owned(), identifier.as_identifier().clone());
+    }
+
+    /// Adds parameter in builder style
+    pub fn with_param<V: ToJson>(mut self, key: &str, value: V) -> Self {
+        self.add_param(key, value);
+        self
+    }
+
+    /// Adds parameter to the template
+    pub fn add_param<V: ToJson>(&mut self, key: &str, value: V) {
+        self.parameters.insert(key.to_owned(), value.to_json());
+    }
+
+    /// Interpolates the identifiers, returning the `Statement`
+    ///
+    /// Fails if a placeholder has no identifier or is not terminated.
+    pub fn build(self) -> Result<Statement, GraphError> {
+        let mut text = String::with_capacity(self.text.len());
+        let mut rest = &self.text[..];
+
+        while let Some(start) = rest.find("#{") {
+            text.push_str(&rest[..start]);
+            rest = &rest[start + 2..];
+
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => return Err(GraphError::new("Unterminated placeholder in statement template")),
+            };
+
+            let key = &rest[..end];
+            match self.identifiers.get(key) {
+                Some(identifier) => text.push_str(&identifier.escaped()),
+                None => {
+                    return Err(GraphError::new(
+                        &format!("No identifier for placeholder #{{{}}} in statement template", key)));
+                },
+            }
+
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+
+        let mut statement = Statement::new(&text);
+        statement.set_parameters(self.parameters);
+        Ok(statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::Statement;
+
+    #[test]
+    fn validation() {
+        assert!(Identifier::new("").is_err());
+        assert!(Label::new("bad\nlabel").is_err());
+        assert!(RelType::new("KNOWS").is_ok());
+    }
+
+    #[test]
+    fn display_is_escaped() {
+        let label = Label::new("Robert`) DETACH DELETE n //").unwrap();
+        assert_eq!(format!("{}", label), "`Robert``) DETACH DELETE n //`");
+        assert_eq!(label.name(), "Robert`) DETACH DELETE n //");
+    }
+
+    #[test]
+    fn template() {
+        let label = Label::new("Person").unwrap();
+        let rel_type = RelType::new("KNOWS").unwrap();
+
+        let statement = Statement::template("MATCH (a:#{label})-[:#{rel}]->(b:#{label}) RETURN b")
+            .with_identifier("label", &label)
+            .with_identifier("rel", &rel_type)
+            .build()
+            .unwrap();
+
+        assert_eq!(statement.statement(), "MATCH (a:`Person`)-[:`KNOWS`]->(b:`Person`) RETURN b");
+    }
+
+    #[test]
+    fn template_keeps_params() {
+        let statement = Statement::template("MATCH (n {name: {name}}) RETURN n")
+            .with_param("name", "Rust".to_owned())
+            .build()
+            .unwrap();
+
+        assert_eq!(statement.statement(), "MATCH (n {name: {name}}) RETURN n");
+        assert_eq!(statement.parameters().len(), 1);
+    }
+
+    #[test]
+    fn template_errors() {
+        assert!(Statement::template("MATCH (n:#{label}) RETURN n").build().is_err());
+        assert!(Statement::template("MATCH (n:#{label) RETURN n").build().is_err());
+    }
+}
@@ -0,0 +1,786 @@
+//! Builder for cypher statements
+//!
+//! Queries are built from patterns and expressions and rendered into a `Statement`. Labels,
+//! relationship types and property keys are always escaped, and values are sent as parameters
+//! with automatically generated names, `__p0`, `__p1` and so on. Names starting with `__p` are
+//! reserved for them and are rejected by `param`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rusted_cypher::cypher::query::{node, prop, value, Query};
+//! let statement = Query::match_(node("n").label("Person"))
+//!     .where_(prop("n", "age").gt(value(18)))
+//!     .return_(&["n.name"])
+//!     .to_statement();
+//!
+//! assert_eq!(statement.statement(), "MATCH (n:`Person`) WHERE n.`age` > {__p0} RETURN n.name");
+//! assert_eq!(statement.param("__p0").unwrap().as_i64(), Some(18));
+//! ```
+//!
+//! ## Named parameters
+//!
+//! Parameters can also be referenced by name and added to the statement afterwards
+//!
+//! ```
+//! # use rusted_cypher::cypher::query::{node, param, prop, rel, Query};
+//! let statement = Query::match_(node("a").label("Person").outgoing(rel("r").rel_type("KNOWS"), node("b")))
+//!     .where_(prop("a", "name").eq(param("name").unwrap()))
+//!     .return_(&[prop("b", "name").alias("friend")])
+//!     .to_statement()
+//!     .with_param("name", "Alice".to_owned());
+//!
+//! assert_eq!(statement.statement(),
+//!            "MATCH (a:`Person`)-[r:`KNOWS`]->(b) WHERE a.`name` = {name} \
+//!             RETURN b.`name` AS friend");
+//! ```
+
+use std::collections::BTreeMap;
+use rustc_serialize::json::{Json, ToJson};
+
+use ::error::GraphError;
+use super::escape_identifier;
+use super::identifier::is_plain_identifier;
+use super::statement::Statement;
+
+/// Prefix of the generated parameter names
+const VALUE_PREFIX: &'static str = "__p";
+
+/// Escapes a variable name only if it is not a plain identifier
+fn escape_variable(variable: &str) -> String {
+    if is_plain_identifier(variable) {
+        variable.to_owned()
+    } else {
+        escape_identifier(variable)
+    }
+}
+
+/// Collects the parameters while rendering a query
+struct Renderer {
+    params: BTreeMap<String, Json>,
+}
+
+impl Renderer {
+    fn new() -> Self {
+        Renderer { params: BTreeMap::new() }
+    }
+
+    fn add_value(&mut self, value: &Json) -> String {
+        let name = format!("{}{}", VALUE_PREFIX, self.params.len());
+        self.params.insert(name.clone(), value.clone());
+        format!("{{{}}}", name)
+    }
+
+    fn properties(&mut self, properties: &[(String, Expr)]) -> String {
+        if properties.is_empty() {
+            return String::new();
+        }
+
+        let properties: Vec<String> = properties.iter()
+            .map(|(key, value)| format!("{}: {}", escape_identifier(key), value.render(self)))
+            .collect();
+
+        format!(" {{{}}}", properties.join(", "))
+    }
+}
+
+/// An expression used in `WHERE`, `SET`, `RETURN` and property maps
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// Variable bound in a pattern
+    Variable(String),
+    /// Property of a variable
+    Property(String, String),
+    /// Named parameter, to be added to the statement afterwards
+    Param(String),
+    /// Value sent as a parameter with a generated name
+    Value(Json),
+    /// Binary operation, such as comparisons and boolean operators
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+    /// Unary prefix operation
+    Not(Box<Expr>),
+    /// Postfix operation, such as `IS NULL`
+    Postfix(Box<Expr>, &'static str),
+    /// Function call
+    Function(String, Vec<Expr>),
+    /// Cypher text included as is
+    Raw(String),
+}
+
+/// Creates an expression referencing a variable
+pub fn var(name: &str) -> Expr {
+    Expr::Variable(name.to_owned())
+}
+
+/// Creates an expression referencing a property of a variable
+pub fn prop(variable: &str, key: &str) -> Expr {
+    Expr::Property(variable.to_owned(), key.to_owned())
+}
+
+/// Creates an expression referencing a named parameter
+///
+/// Fails if `name` is not a plain identifier, or if it starts with `__p`, the prefix of the
+/// generated parameter names.
+pub fn param(name: &str) -> Result<Expr, GraphError> {
+    if !is_plain_identifier(name) {
+        return Err(GraphError::new(&format!("Invalid parameter name {:?}", name)));
+    }
+
+    if name.starts_with(VALUE_PREFIX) {
+        return Err(GraphError::new(&format!("Parameter name {:?} uses the reserved prefix {:?}",
+                                            name, VALUE_PREFIX)));
+    }
+
+    Ok(Expr::Param(name.to_owned()))
+}
+
+/// Creates an expression holding a value, sent as a parameter with a generated name
+pub fn value<V: ToJson>(value: V) -> Expr {
+    Expr::Value(value.to_json())
+}
+
+/// Creates an expression calling a function, such as `count`, `id` or `apoc.coll.sum`
+///
+/// Fails if `name` is not made of plain identifiers separated by dots.
+pub fn func(name: &str, args: Vec<Expr>) -> Result<Expr, GraphError> {
+    if !name.split('.').all(is_plain_identifier) {
+        return Err(GraphError::new(&format!("Invalid function name {:?}", name)));
+    }
+
+    Ok(Expr::Function(name.to_owned(), args))
+}
+
+/// Creates an expression with cypher text included as is
+///
+/// The text is not escaped, so it must never contain user input.
+pub fn raw(text: &str) -> Expr {
+    Expr::Raw(text.to_owned())
+}
+
+impl Expr {
+    fn binary(self, operator: &'static str, other: Expr) -> Expr {
+        Expr::Binary(Box::new(self), operator, Box::new(other))
+    }
+
+    pub fn eq(self, other: Expr) -> Expr {
+        self.binary("=", other)
+    }
+
+    pub fn ne(self, other: Expr) -> Expr {
+        self.binary("<>", other)
+    }
+
+    pub fn gt(self, other: Expr) -> Expr {
+        self.binary(">", other)
+    }
+
+    pub fn gte(self, other: Expr) -> Expr {
+        self.binary(">=", other)
+    }
+
+    pub fn lt(self, other: Expr) -> Expr {
+        self.binary("<", other)
+    }
+
+    pub fn lte(self, other: Expr) -> Expr {
+        self.binary("<=", other)
+    }
+
+    pub fn in_(self, other: Expr) -> Expr {
+        self.binary("IN", other)
+    }
+
+    pub fn starts_with(self, other: Expr) -> Expr {
+        self.binary("STARTS WITH", other)
+    }
+
+    pub fn ends_with(self, other: Expr) -> Expr {
+        self.binary("ENDS WITH", other)
+    }
+
+    pub fn contains(self, other: Expr) -> Expr {
+        self.binary("CONTAINS", other)
+    }
+
+    pub fn and(self, other: Expr) -> Expr {
+        self.binary("AND", other)
+    }
+
+    pub fn or(self, other: Expr) -> Expr {
+        self.binary("OR", other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+
+    pub fn is_null(self) -> Expr {
+        Expr::Postfix(Box::new(self), "IS NULL")
+    }
+
+    pub fn is_not_null(self) -> Expr {
+        Expr::Postfix(Box::new(self), "IS NOT NULL")
+    }
+
+    /// Names the expression in a `RETURN` or `WITH` clause
+    pub fn alias(self, alias: &str) -> ReturnItem {
+        ReturnItem {
+            expr: self,
+            alias: Some(alias.to_owned()),
+        }
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> String {
+        match *self {
+            Expr::Variable(ref name) => escape_variable(name),
+            Expr::Property(ref variable, ref key) => {
+                format!("{}.{}", escape_variable(variable), escape_identifier(key))
+            },
+            Expr::Param(ref name) => format!("{{{}}}", name),
+            Expr::Value(ref value) => renderer.add_value(value),
+            Expr::Binary(ref left, operator, ref right) => {
+                let left = left.render_operand(renderer);
+                let right = right.render_operand(renderer);
+                format!("{} {} {}", left, operator, right)
+            },
+            Expr::Not(ref expr) => format!("NOT {}", expr.render_operand(renderer)),
+            Expr::Postfix(ref expr, operator) => {
+                format!("{} {}", expr.render_operand(renderer), operator)
+            },
+            Expr::Function(ref name, ref args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.render(renderer)).collect();
+                format!("{}({})", name, args.join(", "))
+            },
+            Expr::Raw(ref text) => text.clone(),
+        }
+    }
+
+    /// Renders the expression, wrapping compound expressions in parentheses
+    fn render_operand(&self, renderer: &mut Renderer) -> String {
+        match *self {
+            Expr::Binary(..) | Expr::Not(..) | Expr::Postfix(..) => {
+                format!("({})", self.render(renderer))
+            },
+            _ => self.render(renderer),
+        }
+    }
+}
+
+/// An item of a `RETURN` or `WITH` clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReturnItem {
+    expr: Expr,
+    alias: Option<String>,
+}
+
+impl ReturnItem {
+    fn render(&self, renderer: &mut Renderer) -> String {
+        match self.alias {
+            Some(ref alias) => format!("{} AS {}", self.expr.render(renderer), escape_variable(alias)),
+            None => self.expr.render(renderer),
+        }
+    }
+}
+
+impl From<Expr> for ReturnItem {
+    fn from(expr: Expr) -> Self {
+        ReturnItem {
+            expr: expr,
+            alias: None,
+        }
+    }
+}
+
+impl From<&str> for ReturnItem {
+    /// Uses the text as is, like `raw`
+    fn from(text: &str) -> Self {
+        ReturnItem::from(raw(text))
+    }
+}
+
+/// A node in a pattern, such as `(n:Person {name: {p0}})`
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePattern {
+    variable: Option<String>,
+    labels: Vec<String>,
+    properties: Vec<(String, Expr)>,
+}
+
+/// Creates a node pattern bound to `variable`
+///
+/// Use an empty string for an anonymous node.
+pub fn node(variable: &str) -> NodePattern {
+    NodePattern {
+        variable: if variable.is_empty() { None } else { Some(variable.to_owned()) },
+        labels: Vec::new(),
+        properties: Vec::new(),
+    }
+}
+
+impl NodePattern {
+    /// Adds a label to the node
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_owned());
+        self
+    }
+
+    /// Adds a property to the node
+    pub fn property(mut self, key: &str, value: Expr) -> Self {
+        self.properties.push((key.to_owned(), value));
+        self
+    }
+
+    /// Continues the pattern with an outgoing relationship, `(a)-[r]->(b)`
+    pub fn outgoing(self, relationship: RelPattern, node: NodePattern) -> Pattern {
+        Pattern::from(self).outgoing(relationship, node)
+    }
+
+    /// Continues the pattern with an incoming relationship, `(a)<-[r]-(b)`
+    pub fn incoming(self, relationship: RelPattern, node: NodePattern) -> Pattern {
+        Pattern::from(self).incoming(relationship, node)
+    }
+
+    /// Continues the pattern with a relationship in any direction, `(a)-[r]-(b)`
+    pub fn related(self, relationship: RelPattern, node: NodePattern) -> Pattern {
+        Pattern::from(self).related(relationship, node)
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> String {
+        let mut text = String::from("(");
+        if let Some(ref variable) = self.variable {
+            text.push_str(&escape_variable(variable));
+        }
+        for label in self.labels.iter() {
+            text.push(':');
+            text.push_str(&escape_identifier(label));
+        }
+        text.push_str(&renderer.properties(&self.properties));
+        text.push(')');
+        text
+    }
+}
+
+/// A relationship in a pattern, such as `[r:KNOWS]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelPattern {
+    variable: Option<String>,
+    types: Vec<String>,
+    properties: Vec<(String, Expr)>,
+}
+
+/// Creates a relationship pattern bound to `variable`
+///
+/// Use an empty string for an anonymous relationship.
+pub fn rel(variable: &str) -> RelPattern {
+    RelPattern {
+        variable: if variable.is_empty() { None } else { Some(variable.to_owned()) },
+        types: Vec::new(),
+        properties: Vec::new(),
+    }
+}
+
+impl RelPattern {
+    /// Adds a type to the relationship, types are alternatives when matching
+    pub fn rel_type(mut self, rel_type: &str) -> Self {
+        self.types.push(rel_type.to_owned());
+        self
+    }
+
+    /// Adds a property to the relationship
+    pub fn property(mut self, key: &str, value: Expr) -> Self {
+        self.properties.push((key.to_owned(), value));
+        self
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> String {
+        let mut text = String::from("[");
+        if let Some(ref variable) = self.variable {
+            text.push_str(&escape_variable(variable));
+        }
+        if !self.types.is_empty() {
+            let types: Vec<String> = self.types.iter().map(|t| escape_identifier(t)).collect();
+            text.push(':');
+            text.push_str(&types.join("|"));
+        }
+        text.push_str(&renderer.properties(&self.properties));
+        text.push(']');
+        text
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+/// A path pattern made of nodes and relationships
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    start: NodePattern,
+    steps: Vec<(Direction, RelPattern, NodePattern)>,
+}
+
+impl From<NodePattern> for Pattern {
+    fn from(node: NodePattern) -> Self {
+        Pattern {
+            start: node,
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl Pattern {
+    pub fn outgoing(mut self, relationship: RelPattern, node: NodePattern) -> Self {
+        self.steps.push((Direction::Outgoing, relationship, node));
+        self
+    }
+
+    pub fn incoming(mut self, relationship: RelPattern, node: NodePattern) -> Self {
+        self.steps.push((Direction::Incoming, relationship, node));
+        self
+    }
+
+    pub fn related(mut self, relationship: RelPattern, node: NodePattern) -> Self {
+        self.steps.push((Direction::Both, relationship, node));
+        self
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> String {
+        let mut text = self.start.render(renderer);
+        for &(direction, ref relationship, ref node) in self.steps.iter() {
+            let relationship = relationship.render(renderer);
+            let (left, right) = match direction {
+                Direction::Outgoing => ("-", "->"),
+                Direction::Incoming => ("<-", "-"),
+                Direction::Both => ("-", "-"),
+            };
+            text.push_str(left);
+            text.push_str(&relationship);
+            text.push_str(right);
+            text.push_str(&node.render(renderer));
+        }
+        text
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Clause {
+    Match(Pattern, bool),
+    Create(Pattern),
+    Merge(Pattern),
+    Where(Expr),
+    Set(Expr, Expr),
+    Delete(Vec<String>, bool),
+    With(Vec<ReturnItem>),
+    Return(Vec<ReturnItem>, bool),
+    OrderBy(Expr, bool),
+    Skip(Expr),
+    Limit(Expr),
+}
+
+/// Builds a cypher statement clause by clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    clauses: Vec<Clause>,
+}
+
+impl Query {
+    fn with_clause(clause: Clause) -> Self {
+        Query { clauses: vec![clause] }
+    }
+
+    fn push(mut self, clause: Clause) -> Self {
+        self.clauses.push(clause);
+        self
+    }
+
+    /// Starts a query with a `MATCH` clause
+    pub fn match_<P: Into<Pattern>>(pattern: P) -> Self {
+        Query::with_clause(Clause::Match(pattern.into(), false))
+    }
+
+    /// Starts a query with an `OPTIONAL MATCH` clause
+    pub fn optional_match<P: Into<Pattern>>(pattern: P) -> Self {
+        Query::with_clause(Clause::Match(pattern.into(), true))
+    }
+
+    /// Starts a query with a `CREATE` clause
+    pub fn create<P: Into<Pattern>>(pattern: P) -> Self {
+        Query::with_clause(Clause::Create(pattern.into()))
+    }
+
+    /// Starts a query with a `MERGE` clause
+    pub fn merge<P: Into<Pattern>>(pattern: P) -> Self {
+        Query::with_clause(Clause::Merge(pattern.into()))
+    }
+
+    /// Adds a `MATCH` clause
+    pub fn and_match<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.push(Clause::Match(pattern.into(), false))
+    }
+
+    /// Adds an `OPTIONAL MATCH` clause
+    pub fn and_optional_match<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.push(Clause::Match(pattern.into(), true))
+    }
+
+    /// Adds a `CREATE` clause
+    pub fn and_create<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.push(Clause::Create(pattern.into()))
+    }
+
+    /// Adds a `MERGE` clause
+    pub fn and_merge<P: Into<Pattern>>(self, pattern: P) -> Self {
+        self.push(Clause::Merge(pattern.into()))
+    }
+
+    /// Adds a `WHERE` clause
+    pub fn where_(self, condition: Expr) -> Self {
+        self.push(Clause::Where(condition))
+    }
+
+    /// Adds a `SET` clause, such as `SET n.name = {p0}`
+    pub fn set(self, target: Expr, value: Expr) -> Self {
+        self.push(Clause::Set(target, value))
+    }
+
+    /// Adds a `DELETE` clause
+    pub fn delete(self, variables: &[&str]) -> Self {
+        let variables = variables.iter().map(|variable| (*variable).to_owned()).collect();
+        self.push(Clause::Delete(variables, false))
+    }
+
+    /// Adds a `DETACH DELETE` clause, which requires neo4j 2.3 or later
+    pub fn detach_delete(self, variables: &[&str]) -> Self {
+        let variables = variables.iter().map(|variable| (*variable).to_owned()).collect();
+        self.push(Clause::Delete(variables, true))
+    }
+
+    /// Adds a `WITH` clause
+    pub fn with<R: Into<ReturnItem> + Clone>(self, items: &[R]) -> Self {
+        self.push(Clause::With(items.iter().cloned().map(Into::into).collect()))
+    }
+
+    /// Adds a `RETURN` clause
+    ///
+    /// Items can be expressions or text, which is used as is.
+    pub fn return_<R: Into<ReturnItem> + Clone>(self, items: &[R]) -> Self {
+        self.push(Clause::Return(items.iter().cloned().map(Into::into).collect(), false))
+    }
+
+    /// Adds a `RETURN DISTINCT` clause
+    pub fn return_distinct<R: Into<ReturnItem> + Clone>(self, items: &[R]) -> Self {
+        self.push(Clause::Return(items.iter().cloned().map(Into::into).collect(), true))
+    }
+
+    /// Orders the results by the expression in ascending order
+    pub fn order_by(self, expr: Expr) -> Self {
+        self.push(Clause::OrderBy(expr, false))
+    }
+
+    /// Orders the results by the expression in descending order
+    pub fn order_by_desc(self, expr: Expr) -> Self {
+        self.push(Clause::OrderBy(expr, true))
+    }
+
+    /// Adds a `SKIP` clause
+    pub fn skip(self, skip: Expr) -> Self {
+        self.push(Clause::Skip(skip))
+    }
+
+    /// Adds a `LIMIT` clause
+    pub fn limit(self, limit: Expr) -> Self {
+        self.push(Clause::Limit(limit))
+    }
+
+    /// Renders the query into a `Statement`
+    pub fn to_statement(&self) -> Statement {
+        let mut renderer = Renderer::new();
+        let mut parts: Vec<String> = Vec::with_capacity(self.clauses.len());
+        let mut previous: Option<&Clause> = None;
+
+        for (index, clause) in self.clauses.iter().enumerate() {
+            let next = self.clauses.get(index + 1);
+            let part = match *clause {
+                Clause::Match(ref pattern, optional) => {
+                    let keyword = if optional { "OPTIONAL MATCH" } else { "MATCH" };
+                    format!("{} {}", keyword, pattern.render(&mut renderer))
+                },
+                Clause::Create(ref pattern) => format!("CREATE {}", pattern.render(&mut renderer)),
+                Clause::Merge(ref pattern) => format!("MERGE {}", pattern.render(&mut renderer)),
+                Clause::Where(ref condition) => {
+                    let keyword = match previous {
+                        Some(&Clause::Where(_)) => "AND",
+                        _ => "WHERE",
+                    };
+                    // Consecutive conditions are joined with AND, so they need parentheses
+                    let condition = match (previous, next) {
+                        (Some(&Clause::Where(_)), _) | (_, Some(&Clause::Where(_))) => {
+                            condition.render_operand(&mut renderer)
+                        },
+                        _ => condition.render(&mut renderer),
+                    };
+                    format!("{} {}", keyword, condition)
+                },
+                Clause::Set(ref target, ref value) => {
+                    let target = target.render(&mut renderer);
+                    let value = value.render(&mut renderer);
+                    match previous {
+                        Some(&Clause::Set(..)) => format!(", {} = {}", target, value),
+                        _ => format!("SET {} = {}", target, value),
+                    }
+                },
+                Clause::Delete(ref variables, detach) => {
+                    let keyword = if detach { "DETACH DELETE" } else { "DELETE" };
+                    let variables: Vec<String> = variables.iter().map(|v| escape_variable(v)).collect();
+                    format!("{} {}", keyword, variables.join(", "))
+                },
+                Clause::With(ref items) => format!("WITH {}", render_items(items, &mut renderer)),
+                Clause::Return(ref items, distinct) => {
+                    let keyword = if distinct { "RETURN DISTINCT" } else { "RETURN" };
+                    format!("{} {}", keyword, render_items(items, &mut renderer))
+                },
+                Clause::OrderBy(ref expr, descending) => {
+                    let expr = expr.render(&mut renderer);
+                    let order = if descending { " DESC" } else { "" };
+                    match previous {
+                        Some(&Clause::OrderBy(..)) => format!(", {}{}", expr, order),
+                        _ => format!("ORDER BY {}{}", expr, order),
+                    }
+                },
+                Clause::Skip(ref skip) => format!("SKIP {}", skip.render(&mut renderer)),
+                Clause::Limit(ref limit) => format!("LIMIT {}", limit.render(&mut renderer)),
+            };
+
+            parts.push(part);
+            previous = Some(clause);
+        }
+
+        let mut text = String::new();
+        for part in parts {
+            if !text.is_empty() && !part.starts_with(',') {
+                text.push(' ');
+            }
+            text.push_str(&part);
+        }
+
+        let mut statement = Statement::new(&text);
+        statement.set_parameters(renderer.params);
+        statement
+    }
+}
+
+fn render_items(items: &[ReturnItem], renderer: &mut Renderer) -> String {
+    let items: Vec<String> = items.iter().map(|item| item.render(renderer)).collect();
+    items.join(", ")
+}
+
+impl From<Query> for Statement {
+    fn from(query: Query) -> Self {
+        query.to_statement()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_where_return() {
+        let statement = Query::match_(node("n").label("Person"))
+            .where_(prop("n", "age").gt(value(18)).and(prop("n", "name").starts_with(value("A".to_owned()))))
+            .return_(&["n.name"])
+            .to_statement();
+
+        assert_eq!(statement.statement(),
+                   "MATCH (n:`Person`) WHERE (n.`age` > {__p0}) AND (n.`name` STARTS WITH {__p1}) \
+                    RETURN n.name");
+        assert_eq!(statement.param("__p0").unwrap().as_i64(), Some(18));
+        assert_eq!(statement.param("__p1").unwrap().as_string(), Some("A"));
+    }
+
+    #[test]
+    fn consecutive_where_and_set() {
+        let statement = Query::match_(node("n").property("name", value("Rust".to_owned())))
+            .where_(prop("n", "safe").eq(value(true)))
+            .where_(prop("n", "level").is_not_null())
+            .set(prop("n", "fast"), value(true))
+            .set(prop("n", "level"), param("level").unwrap())
+            .to_statement();
+
+        assert_eq!(statement.statement(),
+                   "MATCH (n {`name`: {__p0}}) WHERE (n.`safe` = {__p1}) AND (n.`level` IS NOT NULL) \
+                    SET n.`fast` = {__p2}, n.`level` = {level}");
+        assert_eq!(statement.parameters().len(), 3);
+    }
+
+    #[test]
+    fn relationship_patterns() {
+        let statement = Query::match_(node("a").label("Person")
+                                          .outgoing(rel("").rel_type("KNOWS").rel_type("LIKES"), node("b"))
+                                          .incoming(rel("r"), node("")))
+            .return_distinct(&[var("b")])
+            .order_by(prop("b", "name"))
+            .order_by_desc(func("id", vec![var("b")]).unwrap())
+            .skip(value(10))
+            .limit(value(5))
+            .to_statement();
+
+        assert_eq!(statement.statement(),
+                   "MATCH (a:`Person`)-[:`KNOWS`|`LIKES`]->(b)<-[r]-() RETURN DISTINCT b \
+                    ORDER BY b.`name`, id(b) DESC SKIP {__p0} LIMIT {__p1}");
+    }
+
+    #[test]
+    fn create_merge_and_delete() {
+        let statement = Query::create(node("n").label("Lang").property("name", value("Rust".to_owned())))
+            .and_merge(node("m").label("Lang").property("name", value("C".to_owned())))
+            .and_create(node("n").outgoing(rel("r").rel_type("INFLUENCED_BY"), node("m")))
+            .with(&[var("n").alias("lang")])
+            .detach_delete(&["lang"])
+            .to_statement();
+
+        assert_eq!(statement.statement(),
+                   "CREATE (n:`Lang` {`name`: {__p0}}) MERGE (m:`Lang` {`name`: {__p1}}) \
+                    CREATE (n)-[r:`INFLUENCED_BY`]->(m) WITH n AS lang DETACH DELETE lang");
+    }
+
+    #[test]
+    fn identifier_escaping() {
+        let statement = Query::match_(node("my node").label("Odd`Label"))
+            .return_(&[prop("my node", "some key")])
+            .to_statement();
+
+        assert_eq!(statement.statement(),
+                   "MATCH (`my node`:`Odd``Label`) RETURN `my node`.`some key`");
+    }
+
+    #[test]
+    fn generated_names_do_not_clash_with_params() {
+        let statement = Query::match_(node("n").property("a", value(1)))
+            .where_(prop("n", "b").eq(param("p0").unwrap()))
+            .return_(&[func("apoc.coll.sum", vec![prop("n", "c")]).unwrap()])
+            .to_statement()
+            .with_param("p0", 2);
+
+        assert_eq!(statement.statement(),
+                   "MATCH (n {`a`: {__p0}}) WHERE n.`b` = {p0} RETURN apoc.coll.sum(n.`c`)");
+        assert_eq!(statement.param("__p0").unwrap().as_i64(), Some(1));
+        assert_eq!(statement.param("p0").unwrap().as_i64(), Some(2));
+    }
+
+    #[test]
+    fn invalid_param_and_function_names() {
+        let error = param("x}) DETACH DELETE n //").unwrap_err();
+        assert!(error.to_string().contains("Invalid parameter name"));
+
+        let error = param("__p0").unwrap_err();
+        assert!(error.to_string().contains("reserved prefix"));
+
+        let error = func("count(n)) DETACH DELETE n //", vec![]).unwrap_err();
+        assert!(error.to_string().contains("Invalid function name"));
+    }
+}
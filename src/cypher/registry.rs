@@ -0,0 +1,489 @@
+//! Named statements loaded from `.cypher` files
+//!
+//! A file may hold several statements, each one starting with a `// name: ...` header, optionally
+//! followed by a `// params: ...` header declaring its parameters. A file without headers holds a
+//! single statement named after the file.
+//!
+//! ```text
+//! // name: create_person
+//! // params: name, age
+//! CREATE (n:Person {name: {name}, age: {age}});
+//!
+//! // name: find_person
+//! MATCH (n:Person) WHERE n.name = {name} RETURN n;
+//! ```
+//!
+//! When parameters are declared, they must match the ones used in the statement text and the
+//! statements built from it must provide exactly those parameters. Otherwise the parameters used
+//! in the text are required.
+//!
+//! # Examples
+//!
+//! ```
+//! # use rusted_cypher::cypher::registry::StatementRegistry;
+//! let registry = StatementRegistry::from_source("
+//!     // name: find_person
+//!     // params: name
+//!     MATCH (n:Person) WHERE n.name = {name} RETURN n;
+//! ").unwrap();
+//!
+//! let statement = registry.statement("find_person").unwrap()
+//!     .with_param("name", "Alice".to_owned());
+//!
+//! assert!(registry.validate("find_person", &statement).is_ok());
+//! ```
+//!
+//! Statements can also be embedded at compile time with the `include_cypher!` macro:
+//!
+//! ```ignore
+//! let statement = include_cypher!("queries/person.cypher", "find_person" {
+//!     "name" => "Alice".to_owned()
+//! }).unwrap();
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use rustc_serialize::json::Json;
+
+use ::error::GraphError;
+use super::identifier::StatementTemplate;
+use super::statement::Statement;
+
+/// Loads the statements of a `.cypher` file at compile time
+///
+/// With only the path, returns a `Result<StatementRegistry, GraphError>`. Given also a name, and
+/// optionally the parameters in the same format as `cypher_stmt!`, returns a
+/// `Result<Statement, GraphError>` with the parameters validated. The path is relative to the
+/// current file, as in `include_str!`.
+#[macro_export]
+macro_rules! include_cypher {
+    ( $path:expr ) => {
+        $crate::cypher::registry::StatementRegistry::from_source(include_str!($path))
+    };
+    ( $path:expr, $name:expr ) => {
+        $crate::include_cypher!($path).and_then(|registry| {
+            let statement = registry.statement($name)?;
+            registry.validate($name, &statement).map(|_| statement)
+        })
+    };
+    ( $path:expr, $name:tt { $( $k:expr => $v:expr ),+ } ) => {
+        $crate::include_cypher!($path).and_then(|registry| {
+            let statement = registry.statement($name)?
+                $(.with_param($k, $v))*;
+            registry.validate($name, &statement).map(|_| statement)
+        })
+    };
+}
+
+/// A statement loaded from a `.cypher` file
+#[derive(Clone, Debug)]
+pub struct NamedStatement {
+    name: String,
+    text: String,
+    params: Vec<String>,
+    declared: bool,
+}
+
+impl NamedStatement {
+    fn new(name: &str, text: &str, declared: Option<Vec<String>>) -> Result<Self, GraphError> {
+        let text = text.trim().trim_end_matches(';').trim_end();
+
+        if name.is_empty() {
+            return Err(GraphError::new("Statement name must not be empty"));
+        }
+
+        if text.is_empty() {
+            return Err(GraphError::new(&format!("Statement '{}' is empty", name)));
+        }
+
+        let used = used_params(text);
+        let (params, declared) = match declared {
+            Some(declared) => {
+                let declared: BTreeSet<String> = declared.into_iter().collect();
+                if declared != used {
+                    let used: Vec<&str> = used.iter().map(|p| &p[..]).collect();
+                    return Err(GraphError::new(
+                        &format!("Statement '{}' uses parameters [{}], which differ from the declared ones",
+                                 name, used.join(", "))));
+                }
+                (declared, true)
+            },
+            None => (used, false),
+        };
+
+        Ok(NamedStatement {
+            name: name.to_owned(),
+            text: text.to_owned(),
+            params: params.into_iter().collect(),
+            declared: declared,
+        })
+    }
+
+    /// Returns the name of the statement
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the statement text, without the headers and the trailing `;`
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the parameters required by the statement
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Returns a new `Statement` without parameters
+    pub fn statement(&self) -> Statement {
+        Statement::new(&self.text)
+    }
+
+    /// Returns a new `StatementTemplate`, to interpolate identifiers in the statement text
+    pub fn template(&self) -> StatementTemplate {
+        Statement::template(&self.text)
+    }
+
+    /// Checks that the statement has all the required parameters
+    ///
+    /// If the parameters were declared, also checks that there are no other parameters.
+    pub fn validate(&self, statement: &Statement) -> Result<(), GraphError> {
+        let missing: Vec<&str> = self.params.iter()
+            .filter(|param| statement.param(param).is_none())
+            .map(|param| &param[..])
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(GraphError::new(&format!("Statement '{}' is missing parameters: {}",
+                                                self.name, missing.join(", "))));
+        }
+
+        if self.declared {
+            let unknown: Vec<&str> = statement.parameters().keys()
+                .filter(|key| !self.params.contains(key))
+                .map(|key| &key[..])
+                .collect();
+
+            if !unknown.is_empty() {
+                return Err(GraphError::new(&format!("Statement '{}' has undeclared parameters: {}",
+                                                    self.name, unknown.join(", "))));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a new `Statement` with the given parameters, after validating them
+    pub fn bind(&self, params: BTreeMap<String, Json>) -> Result<Statement, GraphError> {
+        let mut statement = self.statement();
+        statement.set_parameters(params);
+        self.validate(&statement)?;
+        Ok(statement)
+    }
+}
+
+/// Holds named statements loaded from files or strings
+#[derive(Clone, Debug, Default)]
+pub struct StatementRegistry {
+    statements: BTreeMap<String, NamedStatement>,
+}
+
+impl StatementRegistry {
+    pub fn new() -> Self {
+        StatementRegistry::default()
+    }
+
+    /// Creates a registry with the statements in `source`
+    pub fn from_source(source: &str) -> Result<Self, GraphError> {
+        let mut registry = StatementRegistry::new();
+        registry.add_source(source)?;
+        Ok(registry)
+    }
+
+    /// Adds the statements in `source`, which must all have a `// name: ...` header
+    pub fn add_source(&mut self, source: &str) -> Result<(), GraphError> {
+        for statement in parse(source, None)? {
+            self.insert(statement)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the statements in the given file
+    ///
+    /// If the file has no `// name: ...` header, it holds a single statement named after the file.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), GraphError> {
+        let path = path.as_ref();
+        let mut source = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut source))?;
+
+        let default_name = path.file_stem().and_then(|stem| stem.to_str());
+        for statement in parse(&source, default_name)? {
+            self.insert(statement)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the statements in every `.cypher` file of the given directory, in file name order
+    pub fn load_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<(), GraphError> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "cypher") {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+        for path in paths {
+            self.load_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the statement with the given name
+    pub fn get(&self, name: &str) -> Option<&NamedStatement> {
+        self.statements.get(name)
+    }
+
+    /// Returns the names of all statements, in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        self.statements.keys().map(|name| &name[..]).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Returns a new `Statement` from the statement with the given name
+    pub fn statement(&self, name: &str) -> Result<Statement, GraphError> {
+        self.named(name).map(|statement| statement.statement())
+    }
+
+    /// Returns a new `StatementTemplate` from the statement with the given name
+    pub fn template(&self, name: &str) -> Result<StatementTemplate, GraphError> {
+        self.named(name).map(|statement| statement.template())
+    }
+
+    /// Validates the parameters of `statement` against the statement with the given name
+    pub fn validate(&self, name: &str, statement: &Statement) -> Result<(), GraphError> {
+        self.named(name).and_then(|named| named.validate(statement))
+    }
+
+    fn named(&self, name: &str) -> Result<&NamedStatement, GraphError> {
+        self.statements.get(name)
+            .ok_or_else(|| GraphError::new(&format!("No statement named '{}'", name)))
+    }
+
+    fn insert(&mut self, statement: NamedStatement) -> Result<(), GraphError> {
+        if self.statements.contains_key(statement.name()) {
+            return Err(GraphError::new(&format!("Duplicate statement name '{}'", statement.name())));
+        }
+
+        self.statements.insert(statement.name().to_owned(), statement);
+        Ok(())
+    }
+}
+
+struct Pending<'a> {
+    name: &'a str,
+    params: Option<Vec<String>>,
+    text: String,
+}
+
+fn parse(source: &str, default_name: Option<&str>) -> Result<Vec<NamedStatement>, GraphError> {
+    let has_headers = source.lines().any(|line| header(line, "name").is_some());
+
+    if !has_headers {
+        return match default_name {
+            Some(name) => NamedStatement::new(name, source, None).map(|statement| vec![statement]),
+            None => Err(GraphError::new("Statements must start with a '// name: ...' header")),
+        };
+    }
+
+    let mut statements = Vec::new();
+    let mut pending: Option<Pending> = None;
+
+    for line in source.lines() {
+        if let Some(name) = header(line, "name") {
+            if let Some(done) = pending.take() {
+                statements.push(NamedStatement::new(done.name, &done.text, done.params)?);
+            }
+
+            pending = Some(Pending { name: name, params: None, text: String::new() });
+            continue;
+        }
+
+        match pending {
+            Some(ref mut pending) => {
+                if pending.text.trim().is_empty() {
+                    if let Some(params) = header(line, "params") {
+                        pending.params = Some(params.split(',')
+                            .map(|param| param.trim().to_owned())
+                            .filter(|param| !param.is_empty())
+                            .collect());
+                        continue;
+                    }
+                }
+
+                pending.text.push_str(line);
+                pending.text.push('\n');
+            },
+            None => {
+                let line = line.trim();
+                if !line.is_empty() && !line.starts_with("//") {
+                    return Err(GraphError::new("Statements must start with a '// name: ...' header"));
+                }
+            },
+        }
+    }
+
+    if let Some(done) = pending {
+        statements.push(NamedStatement::new(done.name, &done.text, done.params)?);
+    }
+
+    Ok(statements)
+}
+
+fn header<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let line = line.trim();
+    if !line.starts_with("//") {
+        return None;
+    }
+
+    let line = line[2..].trim_start();
+    if line.starts_with(key) && line[key.len()..].trim_start().starts_with(':') {
+        let value = line[key.len()..].trim_start();
+        Some(value[1..].trim())
+    } else {
+        None
+    }
+}
+
+/// Finds the `{param}` and `$param` parameters in the statement text, skipping string literals,
+/// escaped identifiers, comments and `#{...}` identifier placeholders
+fn used_params(text: &str) -> BTreeSet<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut params = BTreeSet::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            quote @ '\'' | quote @ '"' | quote @ '`' => {
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' && quote != '`' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '{' if i == 0 || chars[i - 1] != '#' => {
+                let name = identifier_at(&chars, i + 1);
+                let end = i + 1 + name.chars().count();
+                if !name.is_empty() && chars.get(end) == Some(&'}') {
+                    params.insert(name);
+                    i = end;
+                }
+            },
+            '$' => {
+                let name = identifier_at(&chars, i + 1);
+                i += name.chars().count();
+                if !name.is_empty() {
+                    params.insert(name);
+                }
+            },
+            _ => {},
+        }
+
+        i += 1;
+    }
+
+    params
+}
+
+fn identifier_at(chars: &[char], start: usize) -> String {
+    chars[start..].iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::used_params;
+
+    #[test]
+    fn parse_named_statements() {
+        let registry = StatementRegistry::from_source("
+            // queries for people
+            // name: create_person
+            // params: name, age
+            CREATE (n:Person {name: {name}, age: {age}});
+
+            // name: count_people
+            MATCH (n:Person) RETURN count(n);
+        ").unwrap();
+
+        assert_eq!(vec!["count_people", "create_person"], registry.names());
+
+        let create = registry.get("create_person").unwrap();
+        assert_eq!("CREATE (n:Person {name: {name}, age: {age}})", create.text());
+        assert_eq!(&["age".to_owned(), "name".to_owned()], create.params());
+        assert!(registry.get("count_people").unwrap().params().is_empty());
+    }
+
+    #[test]
+    fn source_errors() {
+        assert!(StatementRegistry::from_source("MATCH (n) RETURN n").is_err());
+        assert!(StatementRegistry::from_source("// name: empty\n").is_err());
+        assert!(StatementRegistry::from_source("// name: a\nRETURN 1\n// name: a\nRETURN 2").is_err());
+        assert!(StatementRegistry::from_source("// name: a\n// params: x\nRETURN {y}").is_err());
+    }
+
+    #[test]
+    fn find_used_params() {
+        let params = used_params("MATCH (n:#{label} {name: {name}}) WHERE n.age > $age \
+                                  AND n.note <> '{not_a_param}' // {comment}\nRETURN n");
+        let params: Vec<&str> = params.iter().map(|p| &p[..]).collect();
+        assert_eq!(vec!["age", "name"], params);
+    }
+
+    #[test]
+    fn validate_params() {
+        let registry = StatementRegistry::from_source("
+            // name: declared
+            // params: name
+            MATCH (n) WHERE n.name = {name} RETURN n
+
+            // name: undeclared
+            MATCH (n) WHERE n.name = {name} RETURN n
+        ").unwrap();
+
+        let statement = registry.statement("declared").unwrap();
+        assert!(registry.validate("declared", &statement).is_err());
+
+        let statement = statement.with_param("name", "Alice".to_owned());
+        assert!(registry.validate("declared", &statement).is_ok());
+
+        let statement = statement.with_param("other", 1);
+        assert!(registry.validate("declared", &statement).is_err());
+        assert!(registry.validate("undeclared", &statement).is_ok());
+
+        assert!(registry.statement("unknown").is_err());
+    }
+}
@@ -1,19 +1,23 @@
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use rustc_serialize::Decodable;
+use rustc_serialize::json::{self, Json};
 
-use ::error::Neo4jError;
+use ::error::{GraphError, Neo4jError};
 
 pub trait ResultTrait<T: Decodable> {
     fn results(&self) -> &Vec<CypherResult<T>>;
     fn errors(&self) -> &Vec<Neo4jError>;
 }
 
-#[derive(Debug, RustcDecodable)]
+#[derive(Debug)]
 pub struct QueryResult<T: Decodable> {
     pub results: Vec<CypherResult<T>>,
     pub errors: Vec<Neo4jError>,
 }
 
+impl_decodable!(QueryResult<T> { results, errors });
+
 impl<T: Decodable> ResultTrait<T> for QueryResult<T> {
     fn results(&self) -> &Vec<CypherResult<T>> {
         &self.results
@@ -25,12 +29,15 @@ impl<T: Decodable> ResultTrait<T> for QueryResult<T> {
 }
 
 /// Holds the result of a cypher query
-#[derive(Clone, Debug, RustcDecodable)]
+#[derive(Clone, Debug)]
 pub struct CypherResult<T: Decodable> {
     pub columns: Vec<String>,
     pub data: Vec<RowResult<T>>,
+    pub stats: Option<QueryStats>,
 }
 
+impl_decodable!(CypherResult<T> { columns, data, stats });
+
 impl<T: Decodable> CypherResult<T> {
     pub fn columns(&self) -> &Vec<String> {
         &self.columns
@@ -40,14 +47,72 @@ impl<T: Decodable> CypherResult<T> {
     pub fn rows(&self) -> &Vec<RowResult<T>> {
         &self.data
     }
+
+    /// Returns the update statistics, if requested with `Statement::with_stats`
+    pub fn stats(&self) -> Option<&QueryStats> {
+        self.stats.as_ref()
+    }
+}
+
+impl<T: Decodable> Default for CypherResult<T> {
+    fn default() -> Self {
+        CypherResult {
+            columns: Vec::new(),
+            data: Vec::new(),
+            stats: None,
+        }
+    }
+}
+
+/// Update statistics of a cypher query
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryStats {
+    pub contains_updates: bool,
+    pub nodes_created: u64,
+    pub nodes_deleted: u64,
+    pub properties_set: u64,
+    pub relationships_created: u64,
+    pub relationship_deleted: u64,
+    pub labels_added: u64,
+    pub labels_removed: u64,
+    pub indexes_added: u64,
+    pub indexes_removed: u64,
+    pub constraints_added: u64,
+    pub constraints_removed: u64,
+}
+
+impl_decodable!(QueryStats {
+    contains_updates, nodes_created, nodes_deleted, properties_set, relationships_created,
+    relationship_deleted, labels_added, labels_removed, indexes_added, indexes_removed,
+    constraints_added, constraints_removed,
+});
+
+impl QueryStats {
+    /// Adds the counters of `other` to these
+    pub fn add(&mut self, other: &QueryStats) {
+        self.contains_updates |= other.contains_updates;
+        self.nodes_created += other.nodes_created;
+        self.nodes_deleted += other.nodes_deleted;
+        self.properties_set += other.properties_set;
+        self.relationships_created += other.relationships_created;
+        self.relationship_deleted += other.relationship_deleted;
+        self.labels_added += other.labels_added;
+        self.labels_removed += other.labels_removed;
+        self.indexes_added += other.indexes_added;
+        self.indexes_removed += other.indexes_removed;
+        self.constraints_added += other.constraints_added;
+        self.constraints_removed += other.constraints_removed;
+    }
 }
 
 /// Holds a single row of the result of a cypher query
-#[derive(Clone, Debug, RustcDecodable)]
+#[derive(Clone, Debug)]
 pub struct RowResult<T: Decodable> {
     pub row: T,
 }
 
+impl_decodable!(RowResult<T> { row });
+
 impl<T: Decodable> RowResult<T> {
     pub fn data(&self) -> &T {
         &self.row
@@ -62,6 +127,186 @@ impl<T: Decodable> Deref for RowResult<T> {
     }
 }
 
+/// Holds the result of a cypher query with the values as `Json`
+///
+/// Used when the types of the columns are not known in advance, e.g. when exporting results.
+#[derive(Clone, Debug, Default)]
+pub struct RawResult {
+    pub columns: Vec<String>,
+    pub data: Vec<RawRow>,
+    pub stats: Option<QueryStats>,
+}
+
+impl RawResult {
+    /// Parses a single result of the response of the transaction endpoint
+    pub fn from_json(json: &Json) -> Result<Self, GraphError> {
+        let columns = json.find("columns").and_then(|columns| columns.as_array())
+            .map(|columns| strings(columns))
+            .unwrap_or_default();
+
+        let data = json.find("data").and_then(|data| data.as_array())
+            .map(|data| data.iter().map(RawRow::from_json).collect())
+            .unwrap_or_default();
+
+        let stats = match json.find("stats") {
+            Some(stats) => Some(Decodable::decode(&mut json::Decoder::new(stats.clone()))?),
+            None => None,
+        };
+
+        Ok(RawResult {
+            columns: columns,
+            data: data,
+            stats: stats,
+        })
+    }
+
+    pub fn columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &Vec<RawRow> {
+        &self.data
+    }
+}
+
+/// Holds a single row of a `RawResult`
+#[derive(Clone, Debug, Default)]
+pub struct RawRow {
+    pub row: Vec<Json>,
+    /// Nodes and relationships of the row, if requested with `Statement::with_graph`
+    pub graph: Option<ResultGraph>,
+}
+
+impl RawRow {
+    fn from_json(json: &Json) -> Self {
+        RawRow {
+            row: json.find("row").and_then(|row| row.as_array()).cloned().unwrap_or(Vec::new()),
+            graph: json.find("graph").map(ResultGraph::from_json),
+        }
+    }
+}
+
+/// Nodes and relationships of a row
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultGraph {
+    pub nodes: Vec<ResultNode>,
+    pub relationships: Vec<ResultRelationship>,
+}
+
+impl ResultGraph {
+    fn from_json(json: &Json) -> Self {
+        let nodes = json.find("nodes").and_then(|nodes| nodes.as_array())
+            .map(|nodes| nodes.iter().map(ResultNode::from_json).collect())
+            .unwrap_or_default();
+
+        let relationships = json.find("relationships").and_then(|rels| rels.as_array())
+            .map(|rels| rels.iter().map(ResultRelationship::from_json).collect())
+            .unwrap_or_default();
+
+        ResultGraph {
+            nodes: nodes,
+            relationships: relationships,
+        }
+    }
+}
+
+/// A node of a `ResultGraph`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultNode {
+    pub id: String,
+    pub labels: Vec<String>,
+    pub properties: BTreeMap<String, Json>,
+}
+
+impl ResultNode {
+    fn from_json(json: &Json) -> Self {
+        ResultNode {
+            id: json.find("id").map(id_string).unwrap_or_default(),
+            labels: json.find("labels").and_then(|labels| labels.as_array())
+                .map(|labels| strings(labels))
+                .unwrap_or_default(),
+            properties: properties(json),
+        }
+    }
+}
+
+/// A relationship of a `ResultGraph`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultRelationship {
+    pub id: String,
+    pub rel_type: String,
+    pub start: String,
+    pub end: String,
+    pub properties: BTreeMap<String, Json>,
+}
+
+impl ResultRelationship {
+    fn from_json(json: &Json) -> Self {
+        ResultRelationship {
+            id: json.find("id").map(id_string).unwrap_or_default(),
+            rel_type: json.find("type").and_then(|t| t.as_string()).unwrap_or("").to_owned(),
+            start: json.find("startNode").map(id_string).unwrap_or_default(),
+            end: json.find("endNode").map(id_string).unwrap_or_default(),
+            properties: properties(json),
+        }
+    }
+}
+
+fn strings(values: &[Json]) -> Vec<String> {
+    values.iter().filter_map(|value| value.as_string()).map(|value| value.to_owned()).collect()
+}
+
+/// Ids are strings in the graph format, but are accepted as numbers as well
+fn id_string(id: &Json) -> String {
+    match *id {
+        Json::String(ref id) => id.clone(),
+        ref id => id.to_string(),
+    }
+}
+
+fn properties(json: &Json) -> BTreeMap<String, Json> {
+    json.find("properties").and_then(|properties| properties.as_object())
+        .cloned()
+        .unwrap_or(BTreeMap::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use super::*;
+
+    #[test]
+    fn raw_result_from_json() {
+        let json = Json::from_str(r#"{
+            "columns": ["a", "r"],
+            "data": [{
+                "row": [{"name": "Alice"}, {}],
+                "graph": {
+                    "nodes": [{"id": "1", "labels": ["Person"], "properties": {"name": "Alice"}}],
+                    "relationships": [{"id": "7", "type": "KNOWS", "startNode": "1", "endNode": "2", "properties": {}}]
+                }
+            }],
+            "stats": {
+                "contains_updates": false, "nodes_created": 0, "nodes_deleted": 0,
+                "properties_set": 0, "relationships_created": 0, "relationship_deleted": 0,
+                "labels_added": 0, "labels_removed": 0, "indexes_added": 0, "indexes_removed": 0,
+                "constraints_added": 0, "constraints_removed": 0
+            }
+        }"#).unwrap();
+
+        let result = RawResult::from_json(&json).unwrap();
+        assert_eq!(&vec!["a".to_owned(), "r".to_owned()], result.columns());
+        assert_eq!(Some(QueryStats::default()), result.stats);
+
+        let graph = result.rows()[0].graph.as_ref().unwrap();
+        assert_eq!("1", graph.nodes[0].id);
+        assert_eq!(vec!["Person".to_owned()], graph.nodes[0].labels);
+        assert_eq!(("KNOWS", "1", "2"), (&graph.relationships[0].rel_type[..],
+                                         &graph.relationships[0].start[..],
+                                         &graph.relationships[0].end[..]));
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use std::collections::BTreeMap;
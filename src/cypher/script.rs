@@ -0,0 +1,94 @@
+//! Splitting of cypher scripts into statements
+//!
+//! Statements are terminated by `;`. Semicolons inside strings, escaped names and `//` comments
+//! do not terminate a statement.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusted_cypher::cypher::script;
+//!
+//! let statements = script::split_script("CREATE (:Language {name: 'Rust'});\nMATCH (n) RETURN n");
+//! assert_eq!(vec!["CREATE (:Language {name: 'Rust'})", "MATCH (n) RETURN n"], statements);
+//! ```
+
+/// Splits the complete statements, terminated by `;`, from the rest of the buffer
+///
+/// Useful when reading statements line by line, as the rest can be kept until the statement is
+/// terminated.
+pub fn split_statements(buffer: &str) -> (Vec<String>, String) {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut comment = false;
+    let mut escaped = false;
+    let mut chars = buffer.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if comment {
+            comment = c != '\n';
+        } else if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && q != '`' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '/' if chars.peek().map(|&(_, next)| next) == Some('/') => comment = true,
+                ';' => {
+                    if !is_blank(&buffer[start..index]) {
+                        statements.push(buffer[start..index].trim().to_owned());
+                    }
+                    start = index + 1;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    (statements, buffer[start..].to_owned())
+}
+
+/// Splits a whole script into statements, the last of which does not need to be terminated
+pub fn split_script(script: &str) -> Vec<String> {
+    let (mut statements, rest) = split_statements(script);
+    if !is_blank(&rest) {
+        statements.push(rest.trim().to_owned());
+    }
+    statements
+}
+
+/// Checks if the text only has whitespace and lines with comments
+pub fn is_blank(text: &str) -> bool {
+    text.lines().all(|line| line.trim().is_empty() || line.trim().starts_with("//"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_terminated_statements() {
+        let (statements, rest) = split_statements("MATCH (n) RETURN n;\nRETURN ';' // ;\n, 1; MATCH");
+        assert_eq!(vec!["MATCH (n) RETURN n".to_owned(), "RETURN ';' // ;\n, 1".to_owned()],
+                   statements);
+        assert_eq!(" MATCH", rest);
+
+        let (statements, rest) = split_statements("// only a comment;\n");
+        assert!(statements.is_empty());
+        assert_eq!("// only a comment;\n", rest);
+    }
+
+    #[test]
+    fn split_whole_script() {
+        let statements = split_script("// setup\nCREATE (:A {s: \"a;b\"});\n\nMATCH (`a;`) RETURN 1\n// end\n");
+        assert_eq!(vec!["// setup\nCREATE (:A {s: \"a;b\"})".to_owned(),
+                        "MATCH (`a;`) RETURN 1\n// end".to_owned()],
+                   statements);
+        assert!(split_script("// nothing\n\n").is_empty());
+    }
+}
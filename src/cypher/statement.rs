@@ -1,9 +1,12 @@
 use std::collections::BTreeMap;
 use std::convert::From;
-use std::error::Error;
-use rustc_serialize::{Encodable};
+use rustc_serialize::{Encodable, Encoder};
 use rustc_serialize::json::{Json, ToJson};
 
+use super::identifier::StatementTemplate;
+
+const RESULT_DATA_CONTENTS: [&'static str; 2] = ["row", "graph"];
+
 /// Helper macro to simplify the creation of complex statements
 ///
 /// Pass in the statement text as the first argument followed by the (optional) parameters, which
@@ -26,18 +29,26 @@ use rustc_serialize::json::{Json, ToJson};
 /// ```
 #[macro_export]
 macro_rules! cypher_stmt {
-    ( $s:expr ) => { $crate::Statement::new($s) };
-    ( $s:expr { $( $k:expr => $v:expr ),+ } ) => {
-        $crate::Statement::new($s)
+    // `$s:expr` can't be followed by `{`, so the statement is collected token by token until the
+    // parameters, which are the last token tree
+    ( @statement [ $($s:tt)+ ] { $( $k:expr => $v:expr ),+ } ) => {
+        $crate::Statement::new($($s)+)
             $(.with_param($k, $v))*
-    }
+    };
+    ( @statement [ $($s:tt)+ ] ) => { $crate::Statement::new($($s)+) };
+    ( @statement [ $($s:tt)* ] $next:tt $($rest:tt)* ) => {
+        $crate::cypher_stmt!(@statement [ $($s)* $next ] $($rest)*)
+    };
+    ( $($s:tt)+ ) => { $crate::cypher_stmt!(@statement [] $($s)+) };
 }
 
 /// Represents a statement to be sent to the server
-#[derive(Clone, Debug, RustcEncodable)]
+#[derive(Clone, Debug)]
 pub struct Statement {
     statement: String,
     parameters: BTreeMap<String, Json>,
+    include_stats: bool,
+    include_graph: bool,
 }
 
 impl Statement  {
@@ -45,9 +56,20 @@ impl Statement  {
         Statement {
             statement: statement.to_owned(),
             parameters: BTreeMap::new(),
+            include_stats: false,
+            include_graph: false,
         }
     }
 
+    /// Starts a statement whose text has `#{name}` placeholders for labels, relationship types or
+    /// property keys
+    ///
+    /// Only validated identifiers can be interpolated, see the `identifier` module. Values must
+    /// still be passed as parameters.
+    pub fn template(text: &str) -> StatementTemplate {
+        StatementTemplate::new(text)
+    }
+
     /// Returns the statement text
     pub fn statement(&self) -> &str {
         &self.statement
@@ -100,9 +122,67 @@ impl Statement  {
     pub fn remove_param(&mut self, key: &str) {
         self.parameters.remove(key);
     }
+
+    /// Requests the update statistics in builder style
+    ///
+    /// The statistics are available in `CypherResult::stats`
+    pub fn with_stats(mut self) -> Self {
+        self.set_include_stats(true);
+        self
+    }
+
+    /// Sets whether the server should return the update statistics of the statement
+    pub fn set_include_stats(&mut self, include_stats: bool) {
+        self.include_stats = include_stats;
+    }
+
+    /// Returns whether the server should return the update statistics of the statement
+    pub fn include_stats(&self) -> bool {
+        self.include_stats
+    }
+
+    /// Requests the nodes and relationships of each row in builder style
+    ///
+    /// The graph of each row is available in the `RawResult` returned by `Cypher::exec_raw`
+    pub fn with_graph(mut self) -> Self {
+        self.set_include_graph(true);
+        self
+    }
+
+    /// Sets whether the server should return the nodes and relationships of each row
+    pub fn set_include_graph(&mut self, include_graph: bool) {
+        self.include_graph = include_graph;
+    }
+
+    /// Returns whether the server should return the nodes and relationships of each row
+    pub fn include_graph(&self) -> bool {
+        self.include_graph
+    }
+}
+
+impl Encodable for Statement {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        let fields = 2 + self.include_stats as usize + self.include_graph as usize;
+        s.emit_struct("Statement", fields, |s| {
+            s.emit_struct_field("statement", 0, |s| self.statement.encode(s))?;
+            s.emit_struct_field("parameters", 1, |s| self.parameters.encode(s))?;
+
+            let mut index = 2;
+            if self.include_stats {
+                s.emit_struct_field("includeStats", index, |s| s.emit_bool(true))?;
+                index += 1;
+            }
+            if self.include_graph {
+                s.emit_struct_field("resultDataContents", index, |s| {
+                    RESULT_DATA_CONTENTS[..].encode(s)
+                })?;
+            }
+            Ok(())
+        })
+    }
 }
 
-impl<'a> From<&'a str> for Statement {
+impl From<&str> for Statement {
     fn from(stmt: &str) -> Self {
         Statement::new(stmt)
     }
@@ -118,6 +198,25 @@ impl From<String> for Statement {
 mod tests {
     use super::*;
 
+    const MATCH_ALL: &'static str = "MATCH (n) RETURN n";
+
+    #[test]
+    fn macro_with_expressions() {
+        let statement = cypher_stmt!(MATCH_ALL);
+        assert_eq!(MATCH_ALL, statement.statement());
+
+        let label = "Person";
+        let statement = cypher_stmt!(&format!("MATCH (n:{}) WHERE n.name = {{name}} RETURN n",
+                                              label) {
+            "name" => "Alice".to_owned()
+        });
+        assert_eq!("MATCH (n:Person) WHERE n.name = {name} RETURN n", statement.statement());
+        assert_eq!(Some(&Json::String("Alice".to_owned())), statement.param("name"));
+
+        let statement = cypher_stmt!(MATCH_ALL { "a" => 1, "b" => 2 });
+        assert_eq!(2, statement.parameters().len());
+    }
+
     #[test]
     #[allow(unused_variables)]
     fn from_str() {
@@ -159,6 +258,23 @@ mod tests {
         assert_eq!(statement.parameters().len(), 3);
     }
 
+    #[test]
+    fn encode_include_stats_and_graph() {
+        use rustc_serialize::json;
+
+        let statement = Statement::new("MATCH n RETURN n");
+        assert_eq!(r#"{"statement":"MATCH n RETURN n","parameters":{}}"#,
+                   json::encode(&statement).unwrap());
+
+        let statement = statement.with_stats();
+        assert_eq!(r#"{"statement":"MATCH n RETURN n","parameters":{},"includeStats":true}"#,
+                   json::encode(&statement).unwrap());
+
+        let statement = statement.with_graph();
+        assert_eq!(r#"{"statement":"MATCH n RETURN n","parameters":{},"includeStats":true,"resultDataContents":["row","graph"]}"#,
+                   json::encode(&statement).unwrap());
+    }
+
     #[test]
     #[allow(unused_variables)]
     fn macro_without_params() {
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::fmt;
+use std::io;
 use std::string::FromUtf8Error;
 use hyper;
 use rustc_serialize::json;
@@ -7,17 +8,19 @@ use time;
 use url;
 
 
-#[derive(Clone, Debug, PartialEq, RustcDecodable)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Neo4jError {
     pub message: String,
     pub code: String,
 }
 
+impl_decodable!(Neo4jError { message, code });
+
 #[derive(Debug)]
 pub struct GraphError {
     message: String,
     neo4j_errors: Option<Vec<Neo4jError>>,
-    cause: Option<Box<Error>>,
+    cause: Option<Box<dyn Error>>,
 }
 
 impl GraphError {
@@ -37,22 +40,31 @@ impl GraphError {
         }
     }
 
-    pub fn new_error(error: Box<Error>) -> Self {
+    pub fn new_error(error: Box<dyn Error>) -> Self {
         GraphError {
             message: "".to_owned(),
             neo4j_errors: None,
             cause: Some(error),
         }
     }
+
+    /// Returns the errors reported by the server, if this error came from neo4j
+    pub fn neo4j_errors(&self) -> Option<&Vec<Neo4jError>> {
+        self.neo4j_errors.as_ref()
+    }
 }
 
 impl fmt::Display for GraphError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        match self.cause {
+            Some(ref cause) => write!(f, "{}", cause),
+            None => f.write_str(&self.message),
+        }
     }
 }
 
 impl Error for GraphError {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         match self.cause {
             Some(ref cause) => cause.description(),
@@ -60,7 +72,7 @@ impl Error for GraphError {
         }
     }
 
-    fn cause(&self) -> Option<&Error> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self.cause {
             None => None,
             Some(ref e) => Some(&**e)
@@ -78,6 +90,16 @@ impl From<FromUtf8Error> for GraphError {
     }
 }
 
+impl From<io::Error> for GraphError {
+    fn from(error: io::Error) -> Self {
+        GraphError {
+            message: "std::io::Error".to_owned(),
+            neo4j_errors: None,
+            cause: Some(Box::new(error)),
+        }
+    }
+}
+
 impl From<url::ParseError> for GraphError {
     fn from(error: url::ParseError) -> Self {
         GraphError {
@@ -108,6 +130,16 @@ impl From<json::DecoderError> for GraphError {
     }
 }
 
+impl From<json::ParserError> for GraphError {
+    fn from(error: json::ParserError) -> Self {
+        GraphError {
+            message: "rustc_serialize::json::ParserError".to_owned(),
+            neo4j_errors: None,
+            cause: Some(Box::new(error))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TimeParseError(time::ParseError, String);
 
@@ -132,3 +164,21 @@ impl From<time::ParseError> for GraphError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use super::*;
+
+    #[test]
+    fn display_the_cause() {
+        let error = GraphError::from(io::Error::new(io::ErrorKind::NotFound,
+                                                    "/nonexistent.cypher not found"));
+        assert_eq!("/nonexistent.cypher not found", error.to_string());
+
+        let error = GraphError::from(::std::fs::File::open("/nonexistent.cypher").unwrap_err());
+        assert!(!error.to_string().contains("deprecated"));
+
+        assert_eq!("Neo4j Error", GraphError::new_neo4j_error(Vec::new()).to_string());
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::Write;
+use rustc_serialize::json::Json;
+
+use ::error::GraphError;
+use super::RowExporter;
+
+/// Writes rows as CSV, with a header from the columns
+///
+/// Strings are written as they are, `null` as an empty field, and lists and maps as JSON. Fields
+/// are quoted when needed.
+pub struct CsvExporter<W: Write> {
+    writer: W,
+    delimiter: char,
+}
+
+impl<W: Write> CsvExporter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvExporter {
+            writer: writer,
+            delimiter: ',',
+        }
+    }
+
+    /// Sets the field delimiter, `,` by default
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_record<I: Iterator<Item = String>>(&mut self, fields: I) -> Result<(), GraphError> {
+        let delimiter = self.delimiter.to_string();
+        let fields: Vec<String> = fields.map(|field| quote(field, self.delimiter)).collect();
+        writeln!(self.writer, "{}", fields.join(&delimiter))?;
+        Ok(())
+    }
+}
+
+impl<W: Write> RowExporter for CsvExporter<W> {
+    fn write_header(&mut self, columns: &[String]) -> Result<(), GraphError> {
+        self.write_record(columns.iter().cloned())
+    }
+
+    fn write_row(&mut self, row: &[Json]) -> Result<(), GraphError> {
+        self.write_record(row.iter().map(|value| match *value {
+            Json::Null => String::new(),
+            Json::String(ref value) => value.clone(),
+            ref value => value.to_string(),
+        }))
+    }
+
+    fn finish(&mut self) -> Result<(), GraphError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn quote(field: String, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use super::*;
+    use super::super::RowExporter;
+
+    #[test]
+    fn write_csv() {
+        let mut exporter = CsvExporter::new(Vec::new()).with_delimiter(';');
+        exporter.write_header(&["name".to_owned(), "tags".to_owned()]).unwrap();
+        exporter.write_row(&[Json::String("say \"hi\"".to_owned()),
+                             Json::from_str(r#"["a","b"]"#).unwrap()]).unwrap();
+        exporter.write_row(&[Json::String("a;b".to_owned()), Json::Boolean(true)]).unwrap();
+
+        let output = String::from_utf8(exporter.into_inner()).unwrap();
+        assert_eq!("name;tags\n\"say \"\"hi\"\"\";\"[\"\"a\"\",\"\"b\"\"]\"\n\"a;b\";true\n", output);
+    }
+}
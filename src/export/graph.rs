@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write;
+use rustc_serialize::json::Json;
+
+use ::cypher::result::{RawResult, ResultGraph, ResultNode, ResultRelationship};
+use ::error::GraphError;
+
+/// Collects the nodes and relationships of one or more results, without duplicates
+///
+/// Nodes and relationships are kept in the order they are first seen, identified by their id.
+#[derive(Clone, Debug, Default)]
+pub struct GraphCollector {
+    nodes: Vec<ResultNode>,
+    relationships: Vec<ResultRelationship>,
+    node_ids: HashSet<String>,
+    relationship_ids: HashSet<String>,
+}
+
+impl GraphCollector {
+    pub fn new() -> Self {
+        GraphCollector::default()
+    }
+
+    /// Adds the nodes and relationships of a single row
+    pub fn add_graph(&mut self, graph: &ResultGraph) {
+        for node in &graph.nodes {
+            if self.node_ids.insert(node.id.clone()) {
+                self.nodes.push(node.clone());
+            }
+        }
+
+        for relationship in &graph.relationships {
+            if self.relationship_ids.insert(relationship.id.clone()) {
+                self.relationships.push(relationship.clone());
+            }
+        }
+    }
+
+    /// Adds the nodes and relationships of all rows of the result
+    ///
+    /// Rows only contain a graph if the statement was sent with `Statement::with_graph`.
+    pub fn add_result(&mut self, result: &RawResult) {
+        for row in result.rows() {
+            if let Some(ref graph) = row.graph {
+                self.add_graph(graph);
+            }
+        }
+    }
+
+    pub fn nodes(&self) -> &Vec<ResultNode> {
+        &self.nodes
+    }
+
+    pub fn relationships(&self) -> &Vec<ResultRelationship> {
+        &self.relationships
+    }
+}
+
+/// Writes the collected graph as GraphML
+///
+/// Labels are written to the `labels` node attribute, separated by `:`, and relationship types
+/// to the `type` edge attribute. Each property becomes an attribute of its own.
+pub fn write_graphml<W: Write>(graph: &GraphCollector, writer: &mut W) -> Result<(), GraphError> {
+    let node_keys = attributes(graph.nodes.iter().map(|node| &node.properties));
+    let edge_keys = attributes(graph.relationships.iter().map(|rel| &rel.properties));
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(writer, r#"  <key id="labels" for="node" attr.name="labels" attr.type="string"/>"#)?;
+    writeln!(writer, r#"  <key id="type" for="edge" attr.name="type" attr.type="string"/>"#)?;
+
+    for (index, &(ref name, attr_type)) in node_keys.iter().enumerate() {
+        writeln!(writer, r#"  <key id="n{}" for="node" attr.name="{}" attr.type="{}"/>"#,
+                      index, escape(name), attr_type.name())?;
+    }
+
+    for (index, &(ref name, attr_type)) in edge_keys.iter().enumerate() {
+        writeln!(writer, r#"  <key id="e{}" for="edge" attr.name="{}" attr.type="{}"/>"#,
+                      index, escape(name), attr_type.name())?;
+    }
+
+    writeln!(writer, r#"  <graph id="G" edgedefault="directed">"#)?;
+
+    for node in &graph.nodes {
+        writeln!(writer, r#"    <node id="n{}">"#, escape(&node.id))?;
+        writeln!(writer, r#"      <data key="labels">{}</data>"#,
+                      escape(&labels(&node.labels)))?;
+
+        for (index, (name, _)) in node_keys.iter().enumerate() {
+            if let Some(value) = node.properties.get(name) {
+                writeln!(writer, r#"      <data key="n{}">{}</data>"#,
+                              index, escape(&value_string(value)))?;
+            }
+        }
+
+        writeln!(writer, "    </node>")?;
+    }
+
+    for rel in &graph.relationships {
+        writeln!(writer, r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+                      escape(&rel.id), escape(&rel.start), escape(&rel.end))?;
+        writeln!(writer, r#"      <data key="type">{}</data>"#, escape(&rel.rel_type))?;
+
+        for (index, (name, _)) in edge_keys.iter().enumerate() {
+            if let Some(value) = rel.properties.get(name) {
+                writeln!(writer, r#"      <data key="e{}">{}</data>"#,
+                              index, escape(&value_string(value)))?;
+            }
+        }
+
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the collected graph as GEXF
+///
+/// Labels are written to the node `label`, separated by `:`, and relationship types to the edge
+/// `label`. Each property becomes an attribute of its own.
+pub fn write_gexf<W: Write>(graph: &GraphCollector, writer: &mut W) -> Result<(), GraphError> {
+    let node_attributes = attributes(graph.nodes.iter().map(|node| &node.properties));
+    let edge_attributes = attributes(graph.relationships.iter().map(|rel| &rel.properties));
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<gexf xmlns="http://www.gexf.net/1.2draft" version="1.2">"#)?;
+    writeln!(writer, r#"  <graph mode="static" defaultedgetype="directed">"#)?;
+
+    for &(class, attributes) in &[("node", &node_attributes), ("edge", &edge_attributes)] {
+        if attributes.is_empty() {
+            continue;
+        }
+
+        writeln!(writer, r#"    <attributes class="{}">"#, class)?;
+        for (index, &(ref name, attr_type)) in attributes.iter().enumerate() {
+            writeln!(writer, r#"      <attribute id="{}" title="{}" type="{}"/>"#,
+                          index, escape(name), attr_type.name())?;
+        }
+        writeln!(writer, "    </attributes>")?;
+    }
+
+    writeln!(writer, "    <nodes>")?;
+    for node in &graph.nodes {
+        writeln!(writer, r#"      <node id="{}" label="{}">"#,
+                      escape(&node.id), escape(&labels(&node.labels)))?;
+        write_attvalues(writer, &node_attributes, &node.properties)?;
+        writeln!(writer, "      </node>")?;
+    }
+    writeln!(writer, "    </nodes>")?;
+
+    writeln!(writer, "    <edges>")?;
+    for rel in &graph.relationships {
+        writeln!(writer, r#"      <edge id="{}" source="{}" target="{}" label="{}">"#,
+                      escape(&rel.id), escape(&rel.start), escape(&rel.end),
+                      escape(&rel.rel_type))?;
+        write_attvalues(writer, &edge_attributes, &rel.properties)?;
+        writeln!(writer, "      </edge>")?;
+    }
+    writeln!(writer, "    </edges>")?;
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</gexf>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_attvalues<W: Write>(writer: &mut W,
+                             attributes: &[(String, AttrType)],
+                             properties: &BTreeMap<String, Json>)
+    -> Result<(), GraphError>
+{
+    if properties.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "        <attvalues>")?;
+    for (index, (name, _)) in attributes.iter().enumerate() {
+        if let Some(value) = properties.get(name) {
+            writeln!(writer, r#"          <attvalue for="{}" value="{}"/>"#,
+                          index, escape(&value_string(value)))?;
+        }
+    }
+    writeln!(writer, "        </attvalues>")?;
+    Ok(())
+}
+
+/// Type of an attribute, named the same in GraphML and GEXF
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AttrType {
+    Boolean,
+    Long,
+    Double,
+    String,
+}
+
+impl AttrType {
+    fn of(value: &Json) -> Self {
+        match *value {
+            Json::Boolean(_) => AttrType::Boolean,
+            Json::I64(_) | Json::U64(_) => AttrType::Long,
+            Json::F64(_) => AttrType::Double,
+            _ => AttrType::String,
+        }
+    }
+
+    /// The type that can hold values of both types
+    fn merge(self, other: AttrType) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (AttrType::Long, AttrType::Double) | (AttrType::Double, AttrType::Long) => {
+                AttrType::Double
+            },
+            _ => AttrType::String,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            AttrType::Boolean => "boolean",
+            AttrType::Long => "long",
+            AttrType::Double => "double",
+            AttrType::String => "string",
+        }
+    }
+}
+
+/// Names of all properties, sorted, with the type that holds all their values
+fn attributes<'a, I>(properties: I) -> Vec<(String, AttrType)>
+    where I: Iterator<Item = &'a BTreeMap<String, Json>>
+{
+    let properties: Vec<_> = properties.collect();
+    let names: BTreeSet<&String> = properties.iter().flat_map(|props| props.keys()).collect();
+
+    names.into_iter().map(|name| {
+        let attr_type = properties.iter()
+            .filter_map(|props| props.get(name))
+            .filter(|value| !value.is_null())
+            .map(AttrType::of)
+            .fold(None, |acc: Option<AttrType>, t| Some(acc.map_or(t, |acc| acc.merge(t))))
+            .unwrap_or(AttrType::String);
+
+        (name.clone(), attr_type)
+    }).collect()
+}
+
+fn labels(labels: &[String]) -> String {
+    labels.join(":")
+}
+
+/// Strings are written as they are, lists and maps as JSON
+fn value_string(value: &Json) -> String {
+    match *value {
+        Json::String(ref value) => value.clone(),
+        ref value => value.to_string(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use ::cypher::RawResult;
+    use super::*;
+
+    fn collector() -> GraphCollector {
+        let json = Json::from_str(r#"{
+            "columns": ["a", "r", "b"],
+            "data": [
+                {"row": [], "graph": {
+                    "nodes": [
+                        {"id": "1", "labels": ["Person"], "properties": {"name": "A & B", "age": 30}},
+                        {"id": "2", "labels": ["Person", "Admin"], "properties": {"age": 40.5}}
+                    ],
+                    "relationships": [
+                        {"id": "7", "type": "KNOWS", "startNode": "1", "endNode": "2",
+                         "properties": {"since": 2010}}
+                    ]
+                }},
+                {"row": [], "graph": {
+                    "nodes": [{"id": "1", "labels": ["Person"], "properties": {}}],
+                    "relationships": [
+                        {"id": "7", "type": "KNOWS", "startNode": "1", "endNode": "2",
+                         "properties": {}}
+                    ]
+                }}
+            ]
+        }"#).unwrap();
+
+        let mut collector = GraphCollector::new();
+        collector.add_result(&RawResult::from_json(&json).unwrap());
+        collector
+    }
+
+    #[test]
+    fn collect_without_duplicates() {
+        let collector = collector();
+        assert_eq!(2, collector.nodes().len());
+        assert_eq!(1, collector.relationships().len());
+        assert_eq!("A & B", collector.nodes()[0].properties["name"].as_string().unwrap());
+    }
+
+    #[test]
+    fn write_graphml_document() {
+        let mut output = Vec::new();
+        write_graphml(&collector(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(r#"<key id="n0" for="node" attr.name="age" attr.type="double"/>"#));
+        assert!(output.contains(r#"<key id="e0" for="edge" attr.name="since" attr.type="long"/>"#));
+        assert!(output.contains(r#"<data key="labels">Person:Admin</data>"#));
+        assert!(output.contains(r#"<data key="n1">A &amp; B</data>"#));
+        assert!(output.contains(r#"<edge id="e7" source="n1" target="n2">"#));
+        assert!(output.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn write_gexf_document() {
+        let mut output = Vec::new();
+        write_gexf(&collector(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(r#"<attribute id="1" title="name" type="string"/>"#));
+        assert!(output.contains(r#"<node id="2" label="Person:Admin">"#));
+        assert!(output.contains(r#"<attvalue for="1" value="A &amp; B"/>"#));
+        assert!(output.contains(r#"<edge id="7" source="1" target="2" label="KNOWS">"#));
+        assert!(output.ends_with("</gexf>\n"));
+    }
+}
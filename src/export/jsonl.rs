@@ -0,0 +1,80 @@
+use std::io::Write;
+use rustc_serialize::json::Json;
+
+use ::error::GraphError;
+use super::RowExporter;
+
+/// Writes each row as a JSON object in its own line, with the columns as keys
+///
+/// The keys are written in the order of the columns, as in the `RETURN` clause.
+pub struct JsonLinesExporter<W: Write> {
+    writer: W,
+    columns: Vec<String>,
+}
+
+impl<W: Write> JsonLinesExporter<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesExporter {
+            writer: writer,
+            columns: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RowExporter for JsonLinesExporter<W> {
+    fn write_header(&mut self, columns: &[String]) -> Result<(), GraphError> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[Json]) -> Result<(), GraphError> {
+        // a Json::Object would sort the keys
+        let fields: Vec<String> = self.columns.iter()
+            .zip(row)
+            .map(|(column, value)| format!("{}:{}", Json::String(column.clone()), value))
+            .collect();
+
+        writeln!(self.writer, "{{{}}}", fields.join(","))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), GraphError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use super::*;
+    use super::super::RowExporter;
+
+    #[test]
+    fn write_json_lines() {
+        let mut exporter = JsonLinesExporter::new(Vec::new());
+        exporter.write_header(&["name".to_owned(), "age".to_owned()]).unwrap();
+        exporter.write_row(&[Json::String("Alice".to_owned()), Json::U64(30)]).unwrap();
+        exporter.write_row(&[Json::String("Bob".to_owned()), Json::Null]).unwrap();
+
+        let output = String::from_utf8(exporter.into_inner()).unwrap();
+        assert_eq!("{\"name\":\"Alice\",\"age\":30}\n{\"name\":\"Bob\",\"age\":null}\n", output);
+    }
+
+    #[test]
+    fn keys_in_column_order() {
+        let mut exporter = JsonLinesExporter::new(Vec::new());
+        exporter.write_header(&["z".to_owned(), "a \"quoted\" key".to_owned(), "m".to_owned()])
+            .unwrap();
+        exporter.write_row(&[Json::U64(1), Json::Boolean(true),
+                             Json::from_str(r#"{"b": 2, "a": 1}"#).unwrap()]).unwrap();
+
+        let output = String::from_utf8(exporter.into_inner()).unwrap();
+        assert_eq!("{\"z\":1,\"a \\\"quoted\\\" key\":true,\"m\":{\"a\":1,\"b\":2}}\n", output);
+    }
+}
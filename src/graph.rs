@@ -1,15 +1,21 @@
-use std::error::Error;
-use std::io::Read;
-use hyper::{Client, Url};
-use hyper::header::{Authorization, Basic, ContentType, Headers};
-use rustc_serialize::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use hyper::Url;
+use hyper::header::{ContentType, Headers};
+use hyper::method::Method;
+use rustc_serialize::json::{self, Json};
 use semver::Version;
 
-use cypher::Cypher;
+use credentials::Credentials;
+use cypher::{escape_identifier, Cypher, Observer, Tracer};
 use error::GraphError;
+use http_util;
+use rest::{Batch, Nodes, Relationships, Schema};
+use transport::{strip_credentials, BasicAuthTransport, HttpTransport, LoggingTransport, Transport,
+                TransportRequest};
 use cypher::result::{QueryResult, ResultTrait};
 
-#[derive(PartialEq, RustcDecodable)]
+#[derive(PartialEq)]
 #[allow(dead_code)]
 pub struct ServiceRoot {
     pub node: String,
@@ -26,6 +32,20 @@ pub struct ServiceRoot {
     pub neo4j_version: String,
 }
 
+impl_decodable!(ServiceRoot {
+    node, node_index, relationship_index, extensions_info, relationship_types, batch, cypher,
+    indexes, constraints, transaction, node_labels, neo4j_version,
+});
+
+fn decode_string_list(json: Json) -> Vec<String> {
+    match json {
+        Json::Array(values) => values.into_iter()
+            .filter_map(|value| value.as_string().map(|value| value.to_owned()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 fn decode_service_root(json_string: &str) -> Result<ServiceRoot, GraphError> {
     let result = json::decode::<ServiceRoot>(json_string);
 
@@ -37,9 +57,7 @@ fn decode_service_root(json_string: &str) -> Result<ServiceRoot, GraphError> {
     })
 }
 
-#[allow(dead_code)]
 pub struct GraphClient {
-    client: Client,
     headers: Headers,
     service_root: ServiceRoot,
     neo4j_version: Version,
@@ -47,7 +65,21 @@ pub struct GraphClient {
 }
 
 impl GraphClient {
+    /// Connects to the server, logging the statements sent with a default `LoggingTransport`
     pub fn connect(endpoint: &str) -> Result<Self, GraphError> {
+        let transport = LoggingTransport::new(HttpTransport::new());
+        GraphClient::connect_with_transport(endpoint, Arc::new(transport))
+    }
+
+    /// Connects to the server, sending the requests of `Cypher` and transactions through the
+    /// given `Transport`
+    ///
+    /// Credentials in the url are sent in the `Authorization` header, as with `connect`. They are
+    /// kept in `Credentials` and added to each request by a `BasicAuthTransport` wrapping the
+    /// transport, so they never appear in the headers of `Cypher` and `Transaction`.
+    pub fn connect_with_transport(endpoint: &str, transport: Arc<dyn Transport>)
+        -> Result<Self, GraphError>
+    {
         let url = match Url::parse(endpoint) {
             Ok(url) => url,
             Err(e) => {
@@ -56,45 +88,38 @@ impl GraphClient {
             },
         };
 
-        let mut headers = Headers::new();
-
-        url.username().map(|username| url.password().map(|password| {
-            headers.set(Authorization(
-                Basic {
-                    username: username.to_owned(),
-                    password: Some(password.to_owned()),
-                }
-            ));
-        }));
+        let transport = match Credentials::from_url(&url) {
+            Some(credentials) => Arc::new(BasicAuthTransport::new(transport, credentials)),
+            None => transport,
+        };
 
+        let mut headers = Headers::new();
         headers.set(ContentType::json());
 
-        let client = Client::new();
-        let mut res = match client.get(url.clone()).headers(headers.clone()).send() {
+        let request = TransportRequest::new(Method::Get,
+                                            &strip_credentials(&url.to_string()),
+                                            &headers,
+                                            "");
+        let res = match transport.send(&request) {
             Ok(res) => res,
             Err(e) => {
                 error!("Unable to connect to server: {}", e);
-                return Err(GraphError::new_error(Box::new(e)));
+                return Err(e);
             },
         };
 
-        let mut buf = String::new();
-        if let Err(e) = res.read_to_string(&mut buf) {
-            return Err(GraphError::new_error(Box::new(e)));
-        }
-
-        let service_root = try!(decode_service_root(&buf));
+        let service_root = decode_service_root(&res.body)?;
 
         let neo4j_version = match Version::parse(&service_root.neo4j_version) {
             Ok(value) => value,
             Err(e) => return Err(GraphError::new_error(Box::new(e))),
         };
-        let cypher_endpoint = try!(Url::parse(&service_root.transaction));
+        let cypher_endpoint = Url::parse(&service_root.transaction)?;
 
-        let cypher = Cypher::new(cypher_endpoint, headers.clone());
+        let cypher = Cypher::with_transport(cypher_endpoint, headers.clone(), transport)
+            .with_neo4j_version(neo4j_version.clone());
 
         Ok(GraphClient {
-            client: Client::new(),
             headers: headers,
             service_root: service_root,
             neo4j_version: neo4j_version,
@@ -106,10 +131,116 @@ impl GraphClient {
         &self.neo4j_version
     }
 
+    fn get(&self, url: &str) -> Result<Json, GraphError> {
+        http_util::request(&**self.cypher.transport(), Method::Get, url, &self.headers, None)
+    }
+
+    /// Adds an `Observer` in builder style
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.add_observer(observer);
+        self
+    }
+
+    /// Adds an `Observer`, notified of the statements sent by `cypher()` and its transactions
+    pub fn add_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.cypher.add_observer(observer);
+    }
+
+    /// Sets the `Tracer` in builder style
+    pub fn with_tracer(mut self, tracer: Arc<dyn Tracer>) -> Self {
+        self.set_tracer(tracer);
+        self
+    }
+
+    /// Sets the `Tracer` starting the spans of `cypher()` and its transactions
+    pub fn set_tracer(&mut self, tracer: Arc<dyn Tracer>) {
+        self.cypher.set_tracer(tracer);
+    }
+
     /// Returns a reference to the `Cypher` instance of the `GraphClient`
     pub fn cypher(&self) -> &Cypher {
         &self.cypher
     }
+
+    /// Lists all labels in the database
+    pub fn labels(&self) -> Result<Vec<String>, GraphError> {
+        let json = self.get(&self.service_root.node_labels)?;
+        Ok(decode_string_list(json))
+    }
+
+    /// Lists all relationship types in the database
+    pub fn relationship_types(&self) -> Result<Vec<String>, GraphError> {
+        let json = self.get(&self.service_root.relationship_types)?;
+        Ok(decode_string_list(json))
+    }
+
+    /// Lists all property keys in the database
+    ///
+    /// The property keys endpoint is not advertised in the service root, so it is derived from the
+    /// node endpoint. Requires neo4j 2.2 or later.
+    pub fn property_keys(&self) -> Result<Vec<String>, GraphError> {
+        let node = self.service_root.node.trim_end_matches('/');
+        let base = match node.rfind('/') {
+            Some(index) => &node[..index],
+            None => node,
+        };
+
+        let url = format!("{}/propertykeys", base);
+        let json = self.get(&url)?;
+        Ok(decode_string_list(json))
+    }
+
+    /// Counts the nodes with each label in the database
+    pub fn label_counts(&self) -> Result<BTreeMap<String, u64>, GraphError> {
+        let mut counts = BTreeMap::new();
+        for label in self.labels()? {
+            let statement = format!("MATCH (n:{}) RETURN count(n)", escape_identifier(&label));
+            let result: Vec<(u64,)> = self.cypher.exec(statement.into())?;
+            counts.insert(label, result.first().map(|row| row.0).unwrap_or(0));
+        }
+
+        Ok(counts)
+    }
+
+    /// Counts the relationships of each type in the database
+    pub fn relationship_type_counts(&self) -> Result<BTreeMap<String, u64>, GraphError> {
+        let mut counts = BTreeMap::new();
+        for rel_type in self.relationship_types()? {
+            let statement = format!("MATCH ()-[r:{}]->() RETURN count(r)",
+                                    escape_identifier(&rel_type));
+            let result: Vec<(u64,)> = self.cypher.exec(statement.into())?;
+            counts.insert(rel_type, result.first().map(|row| row.0).unwrap_or(0));
+        }
+
+        Ok(counts)
+    }
+
+    /// Returns a `Nodes` to manage nodes through the legacy REST endpoint
+    pub fn nodes(&self) -> Nodes<'_> {
+        Nodes::new(&self.service_root.node, &self.headers)
+            .with_transport(self.cypher.transport().clone())
+    }
+
+    /// Returns a `Relationships` to manage relationships through the legacy REST endpoint
+    pub fn relationships(&self) -> Relationships<'_> {
+        Relationships::new(&self.service_root.node, &self.headers)
+            .with_transport(self.cypher.transport().clone())
+    }
+
+    /// Returns a new `Batch` to submit several REST operations in one call
+    pub fn batch(&self) -> Batch<'_> {
+        Batch::new(&self.service_root.batch, &self.service_root.node, &self.headers)
+            .with_transport(self.cypher.transport().clone())
+    }
+
+    /// Returns a `Schema` to inspect and manage indexes and constraints
+    pub fn schema(&self) -> Schema<'_> {
+        Schema::new(&self.service_root.indexes,
+                    &self.service_root.constraints,
+                    &self.headers,
+                    &self.cypher,
+                    &self.neo4j_version)
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +264,25 @@ mod tests {
         graph.cypher().exec::<()>("MATCH (n:GRAPH_QUERY) RETURN n".into()).unwrap();
     }
 
+    #[test]
+    fn introspection() {
+        let graph = GraphClient::connect(URL).unwrap();
+
+        graph.cypher().exec::<()>(
+            "CREATE (:GRAPH_INTROSPECTION { introspected: true })-[:GRAPH_INTROSPECTED]->()".into()
+        ).unwrap();
+
+        assert!(graph.labels().unwrap().contains(&"GRAPH_INTROSPECTION".to_owned()));
+        assert!(graph.relationship_types().unwrap().contains(&"GRAPH_INTROSPECTED".to_owned()));
+        assert!(graph.property_keys().unwrap().contains(&"introspected".to_owned()));
+        assert_eq!(graph.label_counts().unwrap().get("GRAPH_INTROSPECTION"), Some(&1));
+        assert_eq!(graph.relationship_type_counts().unwrap().get("GRAPH_INTROSPECTED"), Some(&1));
+
+        graph.cypher().exec::<()>(
+            "MATCH (n:GRAPH_INTROSPECTION)-[r]->(m) DELETE r, n, m".into()
+        ).unwrap();
+    }
+
     #[test]
     fn transaction() {
         let graph = GraphClient::connect(URL).unwrap();
@@ -0,0 +1,102 @@
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::status::StatusCode;
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{self, Json};
+
+use ::error::{GraphError, Neo4jError};
+use ::transport::{Transport, TransportRequest};
+
+pub fn request(transport: &dyn Transport, method: Method, url: &str, headers: &Headers, body: Option<&Json>)
+    -> Result<Json, GraphError>
+{
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+
+    debug!("Sending {} {}", method, url);
+
+    let res = transport.send(&TransportRequest::new(method, url, headers, &body))?;
+
+    let status = StatusCode::from_u16(res.status);
+    if !status.is_success() {
+        error!("Request to {} failed with status {}", url, status);
+        // Proxies answer with html pages, which only have the status to report
+        let json = Json::from_str(&res.body).unwrap_or(Json::Null);
+        return Err(decode_error(&json, &status.to_string()));
+    }
+
+    if res.body.trim().is_empty() {
+        return Ok(Json::Null);
+    }
+
+    Ok(Json::from_str(&res.body)?)
+}
+
+pub fn decode_error(json: &Json, status: &str) -> GraphError {
+    if let Some(errors) = json.find("errors") {
+        let mut decoder = json::Decoder::new(errors.clone());
+        if let Ok(errors) = Vec::<Neo4jError>::decode(&mut decoder) {
+            if !errors.is_empty() {
+                return GraphError::new_neo4j_error(errors);
+            }
+        }
+    }
+
+    match json.find("message").and_then(|message| message.as_string()) {
+        Some(message) => GraphError::new(message),
+        None => GraphError::new(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::Headers;
+    use hyper::method::Method;
+    use rustc_serialize::json::Json;
+
+    use ::error::GraphError;
+    use ::transport::{Transport, TransportRequest, TransportResponse};
+
+    struct Respond(u16, &'static str);
+
+    impl Transport for Respond {
+        fn send(&self, _: &TransportRequest) -> Result<TransportResponse, GraphError> {
+            Ok(TransportResponse { status: self.0, location: None, body: self.1.to_owned() })
+        }
+    }
+
+    fn request(status: u16, body: &'static str) -> Result<Json, GraphError> {
+        super::request(&Respond(status, body), Method::Get, "http://localhost:7474/db/data/node/1",
+                       &Headers::new(), None)
+    }
+
+    #[test]
+    fn decode_neo4j_errors() {
+        let error = request(404, r#"{
+            "message": "Cannot find node with id [1] in database.",
+            "errors": [{
+                "message": "Cannot find node with id [1] in database.",
+                "code": "Neo.ClientError.Statement.EntityNotFound"
+            }]
+        }"#).unwrap_err();
+
+        let errors = error.neo4j_errors().unwrap();
+        assert_eq!("Neo.ClientError.Statement.EntityNotFound", errors[0].code);
+        assert_eq!("Cannot find node with id [1] in database.", errors[0].message);
+    }
+
+    #[test]
+    fn status_of_non_json_errors() {
+        let error = request(502, "<html><body><h1>502 Bad Gateway</h1></body></html>")
+            .unwrap_err();
+        assert_eq!("502 Bad Gateway", error.to_string());
+        assert!(error.neo4j_errors().is_none());
+
+        let error = request(503, "").unwrap_err();
+        assert_eq!("503 Service Unavailable", error.to_string());
+    }
+
+    #[test]
+    fn empty_body_on_success() {
+        assert_eq!(Json::Null, request(204, "").unwrap());
+    }
+}
@@ -0,0 +1,115 @@
+//! Minimal CSV reader
+//!
+//! Supports a configurable delimiter, quoted fields with `""` escapes and line breaks inside
+//! quoted fields. Blank lines are skipped.
+
+use std::io::BufRead;
+
+use ::error::GraphError;
+
+pub struct CsvReader<R> {
+    reader: R,
+    delimiter: char,
+    line: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, delimiter: char) -> Self {
+        CsvReader {
+            reader: reader,
+            delimiter: delimiter,
+            line: 0,
+        }
+    }
+
+    /// Returns the line where the last record read ends
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Reads the next record, returning `None` at the end of the input
+    pub fn read_record(&mut self) -> Result<Option<Vec<String>>, GraphError> {
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                if in_quotes {
+                    return Err(GraphError::new(&format!("Unterminated quoted field at line {}",
+                                                        self.line)));
+                }
+                return Ok(None);
+            }
+
+            self.line += 1;
+
+            if !in_quotes && line.trim_end_matches(['\r', '\n']).is_empty() {
+                continue;
+            }
+
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c != '"' {
+                        field.push(c);
+                    } else if chars.peek() == Some(&'"') {
+                        chars.next();
+                        field.push('"');
+                    } else {
+                        in_quotes = false;
+                    }
+                } else if c == '"' && field.is_empty() {
+                    in_quotes = true;
+                } else if c == self.delimiter {
+                    record.push(std::mem::take(&mut field));
+                } else if c != '\r' && c != '\n' {
+                    field.push(c);
+                }
+            }
+
+            if !in_quotes {
+                record.push(field);
+                return Ok(Some(record));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(input: &str, delimiter: char) -> Vec<Vec<String>> {
+        let mut reader = CsvReader::new(input.as_bytes(), delimiter);
+        let mut records = Vec::new();
+        while let Some(record) = reader.read_record().unwrap() {
+            records.push(record);
+        }
+        records
+    }
+
+    #[test]
+    fn read_records() {
+        let records = read_all("a,b,c\r\n1,,3\n\n\"x, \"\"y\"\"\",\"multi\nline\",z\n", ',');
+
+        assert_eq!(vec![
+            vec!["a", "b", "c"],
+            vec!["1", "", "3"],
+            vec!["x, \"y\"", "multi\nline", "z"],
+        ], records);
+    }
+
+    #[test]
+    fn custom_delimiter() {
+        assert_eq!(vec![vec!["a", "b,c"]], read_all("a|b,c", '|'));
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let mut reader = CsvReader::new("\"abc\n".as_bytes(), ',');
+        assert!(reader.read_record().is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Header of node and relationship files
+//!
+//! Each header field has the form `name:type`, where the name may be omitted for the special
+//! types:
+//!
+//! - `id:ID(Space)`: node id, stored in the `id` property, in the optional id space `Space`
+//! - `:LABEL`: labels of the node, separated by the array delimiter
+//! - `:START_ID(Space)` and `:END_ID(Space)`: ids of the start and end nodes of a relationship
+//! - `:TYPE`: type of the relationship
+//! - `:IGNORE`: column not imported
+//! - `name:string`, `name:int`, `name:float`, `name:boolean`: typed property, `string` when the
+//!   type is omitted. `int` also accepts `long`, `short` and `byte`, `float` also accepts `double`.
+//!   Append `[]` for arrays, e.g. `tags:string[]`.
+
+use rustc_serialize::json::Json;
+
+use ::error::GraphError;
+
+/// Type of a property column
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl ValueType {
+    /// Converts the raw value of a field
+    pub fn convert(&self, value: &str) -> Result<Json, String> {
+        match *self {
+            ValueType::String => Ok(Json::String(value.to_owned())),
+            ValueType::Integer => value.trim().parse::<i64>()
+                .map(Json::I64)
+                .map_err(|_| format!("invalid int value '{}'", value)),
+            ValueType::Float => value.trim().parse::<f64>()
+                .map(Json::F64)
+                .map_err(|_| format!("invalid float value '{}'", value)),
+            ValueType::Boolean => match &value.trim().to_lowercase()[..] {
+                "true" => Ok(Json::Boolean(true)),
+                "false" => Ok(Json::Boolean(false)),
+                _ => Err(format!("invalid boolean value '{}'", value)),
+            },
+        }
+    }
+}
+
+/// A column of a node or relationship file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Property { name: String, value_type: ValueType, array: bool },
+    Id { name: String, space: Option<String> },
+    Label,
+    StartId(Option<String>),
+    EndId(Option<String>),
+    Type,
+    Ignore,
+}
+
+impl Column {
+    pub fn parse(field: &str) -> Result<Column, GraphError> {
+        let field = field.trim();
+        let (name, spec) = match field.rfind(':') {
+            Some(index) => (&field[..index], &field[index + 1..]),
+            None => (field, "string"),
+        };
+
+        let (spec, space) = match spec.find('(') {
+            Some(index) if spec.ends_with(')') => {
+                let space = &spec[index + 1..spec.len() - 1];
+                let space = if space.is_empty() { None } else { Some(space.to_owned()) };
+                (&spec[..index], space)
+            },
+            _ => (spec, None),
+        };
+
+        let column = match spec {
+            "ID" => Column::Id {
+                name: if name.is_empty() { "id".to_owned() } else { name.to_owned() },
+                space: space,
+            },
+            "START_ID" => Column::StartId(space),
+            "END_ID" => Column::EndId(space),
+            "LABEL" => Column::Label,
+            "TYPE" => Column::Type,
+            "IGNORE" => Column::Ignore,
+            _ => {
+                let (spec, array) = match spec.strip_suffix("[]") {
+                    Some(spec) => (spec, true),
+                    None => (spec, false),
+                };
+
+                let value_type = match &spec.to_lowercase()[..] {
+                    "" | "string" => ValueType::String,
+                    "int" | "long" | "short" | "byte" => ValueType::Integer,
+                    "float" | "double" => ValueType::Float,
+                    "boolean" => ValueType::Boolean,
+                    _ => return Err(GraphError::new(&format!("Unknown type in column '{}'", field))),
+                };
+
+                if name.is_empty() {
+                    return Err(GraphError::new(&format!("Property column '{}' has no name", field)));
+                }
+
+                Column::Property { name: name.to_owned(), value_type: value_type, array: array }
+            },
+        };
+
+        Ok(column)
+    }
+}
+
+/// Columns of a node or relationship file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    columns: Vec<Column>,
+}
+
+impl Header {
+    pub fn parse(fields: &[String]) -> Result<Self, GraphError> {
+        let mut columns = Vec::new();
+        for field in fields {
+            columns.push(Column::parse(field)?);
+        }
+
+        Ok(Header { columns: columns })
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Checks that the header describes nodes
+    pub fn validate_nodes(&self) -> Result<(), GraphError> {
+        let ids = self.columns.iter().filter(|c| matches!(**c, Column::Id { .. }));
+        if ids.count() > 1 {
+            return Err(GraphError::new("Node header must have at most one ID column"));
+        }
+
+        for column in &self.columns {
+            match *column {
+                Column::StartId(_) | Column::EndId(_) | Column::Type => {
+                    return Err(GraphError::new(
+                        "Node header must not have START_ID, END_ID or TYPE columns"));
+                },
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the header describes relationships
+    pub fn validate_relationships(&self) -> Result<(), GraphError> {
+        let mut starts = 0;
+        let mut ends = 0;
+
+        for column in &self.columns {
+            match *column {
+                Column::StartId(_) => starts += 1,
+                Column::EndId(_) => ends += 1,
+                Column::Id { .. } | Column::Label => {
+                    return Err(GraphError::new(
+                        "Relationship header must not have ID or LABEL columns"));
+                },
+                _ => {},
+            }
+        }
+
+        if starts != 1 || ends != 1 {
+            return Err(GraphError::new(
+                "Relationship header must have exactly one START_ID and one END_ID column"));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the id space of the start and end nodes of relationships
+    pub fn endpoint_spaces(&self) -> (Option<&str>, Option<&str>) {
+        let mut start = None;
+        let mut end = None;
+
+        for column in &self.columns {
+            match *column {
+                Column::StartId(ref space) => start = space.as_ref().map(|s| &s[..]),
+                Column::EndId(ref space) => end = space.as_ref().map(|s| &s[..]),
+                _ => {},
+            }
+        }
+
+        (start, end)
+    }
+
+    /// Returns the id property and id space of nodes, if there is an ID column
+    pub fn id_column(&self) -> Option<(&str, Option<&str>)> {
+        self.columns.iter().filter_map(|column| match *column {
+            Column::Id { ref name, ref space } => Some((&name[..], space.as_ref().map(|s| &s[..]))),
+            _ => None,
+        }).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_columns() {
+        assert_eq!(Column::Id { name: "personId".to_owned(), space: Some("Person".to_owned()) },
+                   Column::parse("personId:ID(Person)").unwrap());
+        assert_eq!(Column::Id { name: "id".to_owned(), space: None }, Column::parse(":ID").unwrap());
+        assert_eq!(Column::StartId(Some("Person".to_owned())),
+                   Column::parse(":START_ID(Person)").unwrap());
+        assert_eq!(Column::Label, Column::parse(":LABEL").unwrap());
+        assert_eq!(Column::Property { name: "name".to_owned(), value_type: ValueType::String, array: false },
+                   Column::parse("name").unwrap());
+        assert_eq!(Column::Property { name: "scores".to_owned(), value_type: ValueType::Float, array: true },
+                   Column::parse("scores:double[]").unwrap());
+
+        assert!(Column::parse("age:integer").is_err());
+        assert!(Column::parse(":int").is_err());
+    }
+
+    #[test]
+    fn convert_values() {
+        assert_eq!(Ok(Json::I64(42)), ValueType::Integer.convert(" 42"));
+        assert_eq!(Ok(Json::F64(1.5)), ValueType::Float.convert("1.5"));
+        assert_eq!(Ok(Json::Boolean(true)), ValueType::Boolean.convert("TRUE"));
+        assert!(ValueType::Integer.convert("4.2").is_err());
+    }
+
+    #[test]
+    fn validate_headers() {
+        let fields = |fields: &[&str]| fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+
+        let nodes = Header::parse(&fields(&["id:ID(Person)", "name", ":LABEL"])).unwrap();
+        assert!(nodes.validate_nodes().is_ok());
+        assert!(nodes.validate_relationships().is_err());
+        assert_eq!(Some(("id", Some("Person"))), nodes.id_column());
+
+        let relationships = Header::parse(&fields(&[":START_ID(Person)", ":END_ID", ":TYPE"])).unwrap();
+        assert!(relationships.validate_relationships().is_ok());
+        assert!(relationships.validate_nodes().is_err());
+        assert_eq!((Some("Person"), None), relationships.endpoint_spaces());
+    }
+}
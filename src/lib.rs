@@ -130,12 +130,28 @@ extern crate semver;
 extern crate time;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "derive")]
+extern crate rusted_cypher_derive;
 
-mod json_util;
+mod http_util;
 
+#[macro_use]
+pub mod codec;
+pub mod credentials;
 pub mod cypher;
 pub mod graph;
 pub mod error;
+pub mod export;
+pub mod fixture;
+pub mod import;
+pub mod migration;
+#[cfg(feature = "testing")]
+pub mod memory;
+pub mod ogm;
+pub mod rest;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transport;
 
 pub use graph::GraphClient;
 pub use cypher::Statement;
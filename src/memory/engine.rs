@@ -0,0 +1,1448 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use time;
+
+use super::{CypherError, ARITHMETIC_ERROR, CONSTRAINT_VALIDATION_FAILED, PARAMETER_MISSING,
+            SYNTAX_ERROR, TYPE_ERROR};
+use super::parser::{BinaryOp, Clause, Direction, Expr, NodePattern, Pattern, Projection,
+                    Query, RemoveItem, SetItem, ANONYMOUS};
+use super::value::{self, Value};
+
+const AGGREGATIONS: &'static [&'static str] = &["count", "sum", "avg", "min", "max", "collect"];
+
+#[derive(Clone, Debug)]
+pub struct NodeData {
+    pub labels: Vec<String>,
+    pub properties: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RelationshipData {
+    pub rel_type: String,
+    pub start: u64,
+    pub end: u64,
+    pub properties: BTreeMap<String, Value>,
+}
+
+/// The nodes and relationships of the graph
+#[derive(Clone, Debug, Default)]
+pub struct Store {
+    pub nodes: BTreeMap<u64, NodeData>,
+    pub relationships: BTreeMap<u64, RelationshipData>,
+    next_node: u64,
+    next_relationship: u64,
+}
+
+/// Counters of the changes made by a statement, as in `QueryStats`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub nodes_created: u64,
+    pub nodes_deleted: u64,
+    pub properties_set: u64,
+    pub relationships_created: u64,
+    pub relationship_deleted: u64,
+    pub labels_added: u64,
+    pub labels_removed: u64,
+}
+
+/// The columns, rows and stats of an executed statement
+#[derive(Debug)]
+pub struct Output {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub stats: Stats,
+}
+
+type Row = BTreeMap<String, Value>;
+
+/// Executes the query against the store
+///
+/// The store may be left partially updated when an error is returned, so callers should execute
+/// against a copy they can discard.
+pub fn execute(store: &mut Store, query: &Query, parameters: &BTreeMap<String, Value>)
+    -> Result<Output, CypherError>
+{
+    let mut executor = Executor {
+        store: store,
+        parameters: parameters,
+        stats: Stats::default(),
+    };
+
+    executor.run(query)
+}
+
+fn type_error(message: &str) -> CypherError {
+    CypherError::new(TYPE_ERROR, message)
+}
+
+fn overflow() -> CypherError {
+    CypherError::new(ARITHMETIC_ERROR, "long overflow")
+}
+
+fn is_aggregation(name: &str) -> bool {
+    AGGREGATIONS.contains(&name)
+}
+
+fn contains_aggregation(expression: &Expr) -> bool {
+    match *expression {
+        Expr::CountStar => true,
+        Expr::Call(ref name, _, ref arguments) => {
+            is_aggregation(name) || arguments.iter().any(contains_aggregation)
+        },
+        Expr::Literal(_) | Expr::Parameter(_) | Expr::Variable(_) => false,
+        Expr::Property(ref e, _) | Expr::HasLabels(ref e, _) | Expr::Not(ref e) |
+        Expr::Negate(ref e) | Expr::IsNull(ref e, _) => contains_aggregation(e),
+        Expr::Index(ref a, ref b) | Expr::And(ref a, ref b) | Expr::Or(ref a, ref b) |
+        Expr::Binary(_, ref a, ref b) => contains_aggregation(a) || contains_aggregation(b),
+        Expr::List(ref values) => values.iter().any(contains_aggregation),
+        Expr::Map(ref entries) => entries.iter().any(|entry| contains_aggregation(&entry.1)),
+    }
+}
+
+fn pattern_variables(patterns: &[Pattern]) -> Vec<String> {
+    let mut variables = Vec::new();
+    for pattern in patterns {
+        variables.push(pattern.start.variable.clone());
+        for (relationship, node) in &pattern.steps {
+            variables.push(relationship.variable.clone());
+            variables.push(node.variable.clone());
+        }
+    }
+
+    variables
+}
+
+fn truth(value: &Value) -> Result<Option<bool>, CypherError> {
+    match *value {
+        Value::Bool(value) => Ok(Some(value)),
+        Value::Null => Ok(None),
+        ref value => Err(type_error(&format!("Expected a Boolean but was {}", value.type_name()))),
+    }
+}
+
+fn integer(value: &Value, what: &str) -> Result<i64, CypherError> {
+    match *value {
+        Value::Int(value) => Ok(value),
+        ref value => Err(type_error(&format!("{} must be an Integer but was {}",
+                                             what, value.type_name()))),
+    }
+}
+
+struct Executor<'a> {
+    store: &'a mut Store,
+    parameters: &'a BTreeMap<String, Value>,
+    stats: Stats,
+}
+
+impl<'a> Executor<'a> {
+    fn run(&mut self, query: &Query) -> Result<Output, CypherError> {
+        let mut rows = vec![Row::new()];
+        let mut variables = BTreeSet::new();
+        let mut columns = Vec::new();
+        let mut result = Vec::new();
+
+        for clause in &query.clauses {
+            rows = match *clause {
+                Clause::Match { optional, ref patterns, ref filter } => {
+                    variables.extend(pattern_variables(patterns));
+                    self.match_rows(rows, optional, patterns, filter.as_ref())?
+                },
+                Clause::Create(ref patterns) => {
+                    variables.extend(pattern_variables(patterns));
+                    let mut created = Vec::with_capacity(rows.len());
+                    for mut row in rows {
+                        for pattern in patterns {
+                            self.create_pattern(&mut row, pattern)?;
+                        }
+                        created.push(row);
+                    }
+                    created
+                },
+                Clause::Merge { ref pattern, ref on_create, ref on_match } => {
+                    variables.extend(pattern_variables(::std::slice::from_ref(pattern)));
+                    self.merge(rows, pattern, on_create, on_match)?
+                },
+                Clause::Set(ref items) => {
+                    for row in &rows {
+                        for item in items {
+                            self.set(row, item)?;
+                        }
+                    }
+                    rows
+                },
+                Clause::Remove(ref items) => {
+                    for row in &rows {
+                        for item in items {
+                            self.remove(row, item)?;
+                        }
+                    }
+                    rows
+                },
+                Clause::Delete { detach, ref expressions } => {
+                    self.delete(&rows, detach, expressions)?;
+                    rows
+                },
+                Clause::Unwind { ref expression, ref variable } => {
+                    variables.insert(variable.clone());
+                    let mut unwound = Vec::new();
+                    for row in rows {
+                        let values = match self.eval(expression, &row)? {
+                            Value::List(values) => values,
+                            Value::Null => Vec::new(),
+                            value => vec![value],
+                        };
+
+                        for value in values {
+                            let mut row = row.clone();
+                            row.insert(variable.clone(), value);
+                            unwound.push(row);
+                        }
+                    }
+                    unwound
+                },
+                Clause::With(ref projection) => {
+                    let (names, values) = self.project(rows, projection, &variables)?;
+                    variables = names.iter().cloned().collect();
+
+                    let mut projected = Vec::with_capacity(values.len());
+                    for values in values {
+                        let row: Row = names.iter().cloned().zip(values).collect();
+                        let keep = match projection.filter {
+                            Some(ref filter) => self.is_true(filter, &row)?,
+                            None => true,
+                        };
+
+                        if keep {
+                            projected.push(row);
+                        }
+                    }
+                    projected
+                },
+                Clause::Return(ref projection) => {
+                    let (names, values) = self.project(rows, projection, &variables)?;
+                    columns = names;
+                    result = values;
+                    Vec::new()
+                },
+                Clause::SetMetadata(ref metadata) => {
+                    for row in &rows {
+                        self.eval(metadata, row)?;
+                    }
+                    rows
+                },
+            };
+        }
+
+        Ok(Output {
+            columns: columns,
+            rows: result,
+            stats: self.stats.clone(),
+        })
+    }
+
+    fn match_rows(&self, rows: Vec<Row>, optional: bool, patterns: &[Pattern], filter: Option<&Expr>)
+        -> Result<Vec<Row>, CypherError>
+    {
+        let mut matched = Vec::new();
+
+        for row in rows {
+            let mut candidates = Vec::new();
+            self.match_patterns(&row, patterns, &mut Vec::new(), &mut candidates)?;
+
+            let mut found = false;
+            for candidate in candidates {
+                if let Some(filter) = filter {
+                    if !self.is_true(filter, &candidate)? {
+                        continue;
+                    }
+                }
+
+                found = true;
+                matched.push(candidate);
+            }
+
+            if !found && optional {
+                let mut row = row;
+                for variable in pattern_variables(patterns) {
+                    row.entry(variable).or_insert(Value::Null);
+                }
+                matched.push(row);
+            }
+        }
+
+        Ok(matched)
+    }
+
+    fn match_patterns(&self, row: &Row, patterns: &[Pattern], used: &mut Vec<u64>,
+                      matched: &mut Vec<Row>) -> Result<(), CypherError>
+    {
+        let pattern = match patterns.first() {
+            Some(pattern) => pattern,
+            None => {
+                matched.push(row.clone());
+                return Ok(());
+            },
+        };
+
+        for (row, relationships) in self.match_pattern(row, pattern, used)? {
+            let length = used.len();
+            used.extend(relationships);
+            self.match_patterns(&row, &patterns[1..], used, matched)?;
+            used.truncate(length);
+        }
+
+        Ok(())
+    }
+
+    /// Matches a single pattern, returning the rows with its variables bound and the
+    /// relationships each of them used
+    fn match_pattern(&self, row: &Row, pattern: &Pattern, used: &[u64])
+        -> Result<Vec<(Row, Vec<u64>)>, CypherError>
+    {
+        let properties = self.pattern_properties(&pattern.start.properties, row)?;
+
+        let candidates: Vec<u64> = match row.get(&pattern.start.variable) {
+            Some(&Value::Node(id)) => vec![id],
+            Some(&Value::Null) => Vec::new(),
+            Some(value) => {
+                return Err(type_error(&format!("Expected `{}` to be a Node but was {}",
+                                               pattern.start.variable, value.type_name())));
+            },
+            None => self.store.nodes.keys().cloned().collect(),
+        };
+
+        let mut matched = Vec::new();
+        for id in candidates {
+            if !self.node_matches(id, &pattern.start, &properties) {
+                continue;
+            }
+
+            let mut row = row.clone();
+            row.insert(pattern.start.variable.clone(), Value::Node(id));
+            self.match_steps(row, id, &pattern.steps, used.to_vec(), &mut matched)?;
+        }
+
+        Ok(matched)
+    }
+
+    fn match_steps(&self, row: Row, current: u64, steps: &[(::memory::parser::RelPattern, NodePattern)],
+                   used: Vec<u64>, matched: &mut Vec<(Row, Vec<u64>)>) -> Result<(), CypherError>
+    {
+        let (relationship, node) = match steps.first() {
+            Some((relationship, node)) => (relationship, node),
+            None => {
+                matched.push((row, used));
+                return Ok(());
+            },
+        };
+
+        let relationship_properties = self.pattern_properties(&relationship.properties, &row)?;
+        let node_properties = self.pattern_properties(&node.properties, &row)?;
+
+        for (&id, data) in &self.store.relationships {
+            if used.contains(&id) {
+                continue;
+            }
+
+            let other = match relationship.direction {
+                Direction::Outgoing if data.start == current => data.end,
+                Direction::Incoming if data.end == current => data.start,
+                Direction::Both if data.start == current => data.end,
+                Direction::Both if data.end == current => data.start,
+                _ => continue,
+            };
+
+            if !relationship.types.is_empty() && !relationship.types.contains(&data.rel_type) {
+                continue;
+            }
+
+            match row.get(&relationship.variable) {
+                Some(&Value::Relationship(bound)) if bound == id => {},
+                Some(_) => continue,
+                None => {},
+            }
+
+            if !properties_match(&data.properties, &relationship_properties) {
+                continue;
+            }
+
+            match row.get(&node.variable) {
+                Some(&Value::Node(bound)) if bound == other => {},
+                Some(_) => continue,
+                None => {},
+            }
+
+            if !self.node_matches(other, node, &node_properties) {
+                continue;
+            }
+
+            let mut row = row.clone();
+            row.insert(relationship.variable.clone(), Value::Relationship(id));
+            row.insert(node.variable.clone(), Value::Node(other));
+
+            let mut used = used.clone();
+            used.push(id);
+            self.match_steps(row, other, &steps[1..], used, matched)?;
+        }
+
+        Ok(())
+    }
+
+    fn node_matches(&self, id: u64, pattern: &NodePattern, properties: &BTreeMap<String, Value>)
+        -> bool
+    {
+        match self.store.nodes.get(&id) {
+            Some(node) => {
+                pattern.labels.iter().all(|label| node.labels.contains(label))
+                    && properties_match(&node.properties, properties)
+            },
+            None => false,
+        }
+    }
+
+    fn pattern_properties(&self, properties: &Option<Expr>, row: &Row)
+        -> Result<BTreeMap<String, Value>, CypherError>
+    {
+        match *properties {
+            Some(ref expression) => match self.eval(expression, row)? {
+                Value::Map(properties) => Ok(properties),
+                value => Err(type_error(&format!("Expected properties to be a Map but was {}",
+                                                 value.type_name()))),
+            },
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    fn merge(&mut self, rows: Vec<Row>, pattern: &Pattern, on_create: &[SetItem],
+             on_match: &[SetItem]) -> Result<Vec<Row>, CypherError>
+    {
+        let mut merged = Vec::new();
+
+        for row in rows {
+            let matched = self.match_pattern(&row, pattern, &[])?;
+
+            if matched.is_empty() {
+                let mut row = row;
+                self.create_pattern(&mut row, pattern)?;
+                for item in on_create {
+                    self.set(&row, item)?;
+                }
+                merged.push(row);
+            } else {
+                for (row, _) in matched {
+                    for item in on_match {
+                        self.set(&row, item)?;
+                    }
+                    merged.push(row);
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    fn create_pattern(&mut self, row: &mut Row, pattern: &Pattern) -> Result<(), CypherError> {
+        let mut current = self.create_node(row, &pattern.start)?;
+
+        for (relationship, node) in &pattern.steps {
+            let next = self.create_node(row, node)?;
+
+            let (start, end) = match relationship.direction {
+                Direction::Outgoing => (current, next),
+                Direction::Incoming => (next, current),
+                Direction::Both => {
+                    return Err(CypherError::new(SYNTAX_ERROR, "Only directed relationships are \
+                                                               supported in CREATE"));
+                },
+            };
+
+            if relationship.types.len() != 1 {
+                return Err(CypherError::new(SYNTAX_ERROR, "Exactly one relationship type must be \
+                                                           specified for CREATE"));
+            }
+
+            if row.contains_key(&relationship.variable) {
+                return Err(CypherError::new(SYNTAX_ERROR, &format!(
+                    "Can't create `{}` because it is already declared", relationship.variable)));
+            }
+
+            let properties = self.property_values(&relationship.properties, row)?;
+            self.stats.relationships_created += 1;
+            self.stats.properties_set += properties.len() as u64;
+
+            let id = self.store.next_relationship;
+            self.store.next_relationship += 1;
+            self.store.relationships.insert(id, RelationshipData {
+                rel_type: relationship.types[0].clone(),
+                start: start,
+                end: end,
+                properties: properties,
+            });
+
+            row.insert(relationship.variable.clone(), Value::Relationship(id));
+            current = next;
+        }
+
+        Ok(())
+    }
+
+    fn create_node(&mut self, row: &mut Row, pattern: &NodePattern) -> Result<u64, CypherError> {
+        if let Some(value) = row.get(&pattern.variable) {
+            if !pattern.labels.is_empty() || pattern.properties.is_some() {
+                return Err(CypherError::new(SYNTAX_ERROR, &format!(
+                    "Can't create node `{}` with labels or properties here. The variable is \
+                     already declared in this context", pattern.variable)));
+            }
+
+            return match *value {
+                Value::Node(id) => Ok(id),
+                ref value => Err(type_error(&format!("Expected `{}` to be a Node but was {}",
+                                                     pattern.variable, value.type_name()))),
+            };
+        }
+
+        let properties = self.property_values(&pattern.properties, row)?;
+
+        let mut labels: Vec<String> = Vec::new();
+        for label in &pattern.labels {
+            if !labels.contains(label) {
+                labels.push(label.clone());
+            }
+        }
+
+        self.stats.nodes_created += 1;
+        self.stats.labels_added += labels.len() as u64;
+        self.stats.properties_set += properties.len() as u64;
+
+        let id = self.store.next_node;
+        self.store.next_node += 1;
+        self.store.nodes.insert(id, NodeData { labels: labels, properties: properties });
+
+        row.insert(pattern.variable.clone(), Value::Node(id));
+        Ok(id)
+    }
+
+    /// Evaluates the properties of a pattern to be created, leaving out nulls
+    fn property_values(&self, properties: &Option<Expr>, row: &Row)
+        -> Result<BTreeMap<String, Value>, CypherError>
+    {
+        let properties = self.pattern_properties(properties, row)?;
+        for value in properties.values() {
+            check_property(value)?;
+        }
+
+        Ok(properties.into_iter().filter(|(_, value)| !value.is_null()).collect())
+    }
+
+    fn properties_mut(&mut self, target: &Value)
+        -> Result<Option<&mut BTreeMap<String, Value>>, CypherError>
+    {
+        match *target {
+            Value::Node(id) => Ok(self.store.nodes.get_mut(&id).map(|node| &mut node.properties)),
+            Value::Relationship(id) => {
+                Ok(self.store.relationships.get_mut(&id).map(|r| &mut r.properties))
+            },
+            Value::Null => Ok(None),
+            ref value => Err(type_error(&format!("Expected a Node or Relationship but was {}",
+                                                 value.type_name()))),
+        }
+    }
+
+    fn variable(&self, row: &Row, name: &str) -> Result<Value, CypherError> {
+        self.eval(&Expr::Variable(name.to_owned()), row)
+    }
+
+    fn set(&mut self, row: &Row, item: &SetItem) -> Result<(), CypherError> {
+        match *item {
+            SetItem::Property(ref variable, ref key, ref expression) => {
+                let target = self.variable(row, variable)?;
+                let value = self.eval(expression, row)?;
+                check_property(&value)?;
+
+                if let Some(properties) = self.properties_mut(&target)? {
+                    if value.is_null() {
+                        if properties.remove(key).is_none() {
+                            return Ok(());
+                        }
+                    } else {
+                        properties.insert(key.clone(), value);
+                    }
+                } else {
+                    return Ok(());
+                }
+
+                self.stats.properties_set += 1;
+            },
+            SetItem::Replace(ref variable, ref expression) |
+            SetItem::Merge(ref variable, ref expression) => {
+                let replace = matches!(*item, SetItem::Replace(..));
+
+                let target = self.variable(row, variable)?;
+                let values = match self.eval(expression, row)? {
+                    Value::Map(values) => values,
+                    Value::Node(id) => {
+                        self.store.nodes.get(&id).map(|n| n.properties.clone()).unwrap_or_default()
+                    },
+                    Value::Relationship(id) => {
+                        self.store.relationships.get(&id)
+                            .map(|r| r.properties.clone())
+                            .unwrap_or_default()
+                    },
+                    value => {
+                        return Err(type_error(&format!("Expected a Map but was {}",
+                                                       value.type_name())));
+                    },
+                };
+
+                for value in values.values() {
+                    check_property(value)?;
+                }
+
+                let mut count = 0;
+                if let Some(properties) = self.properties_mut(&target)? {
+                    if replace {
+                        count += properties.keys().filter(|key| !values.contains_key(*key)).count();
+                        properties.clear();
+                    }
+
+                    for (key, value) in values {
+                        if value.is_null() {
+                            if properties.remove(&key).is_some() {
+                                count += 1;
+                            }
+                        } else {
+                            properties.insert(key, value);
+                            count += 1;
+                        }
+                    }
+                }
+
+                self.stats.properties_set += count as u64;
+            },
+            SetItem::Labels(ref variable, ref labels) => {
+                match self.variable(row, variable)? {
+                    Value::Node(id) => {
+                        if let Some(node) = self.store.nodes.get_mut(&id) {
+                            for label in labels {
+                                if !node.labels.contains(label) {
+                                    node.labels.push(label.clone());
+                                    self.stats.labels_added += 1;
+                                }
+                            }
+                        }
+                    },
+                    Value::Null => {},
+                    value => {
+                        return Err(type_error(&format!("Expected `{}` to be a Node but was {}",
+                                                       variable, value.type_name())));
+                    },
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, row: &Row, item: &RemoveItem) -> Result<(), CypherError> {
+        match *item {
+            RemoveItem::Property(ref variable, ref key) => {
+                let target = self.variable(row, variable)?;
+                let removed = match self.properties_mut(&target)? {
+                    Some(properties) => properties.remove(key).is_some(),
+                    None => false,
+                };
+
+                if removed {
+                    self.stats.properties_set += 1;
+                }
+            },
+            RemoveItem::Labels(ref variable, ref labels) => {
+                match self.variable(row, variable)? {
+                    Value::Node(id) => {
+                        if let Some(node) = self.store.nodes.get_mut(&id) {
+                            let before = node.labels.len();
+                            node.labels.retain(|label| !labels.contains(label));
+                            self.stats.labels_removed += (before - node.labels.len()) as u64;
+                        }
+                    },
+                    Value::Null => {},
+                    value => {
+                        return Err(type_error(&format!("Expected `{}` to be a Node but was {}",
+                                                       variable, value.type_name())));
+                    },
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, rows: &[Row], detach: bool, expressions: &[Expr])
+        -> Result<(), CypherError>
+    {
+        let mut nodes = BTreeSet::new();
+        let mut relationships = BTreeSet::new();
+
+        for row in rows {
+            for expression in expressions {
+                match self.eval(expression, row)? {
+                    Value::Node(id) => { nodes.insert(id); },
+                    Value::Relationship(id) => { relationships.insert(id); },
+                    Value::Null => {},
+                    value => {
+                        return Err(type_error(&format!("Expected a Node or Relationship but \
+                                                        was {}", value.type_name())));
+                    },
+                }
+            }
+        }
+
+        for (&id, relationship) in &self.store.relationships {
+            if relationships.contains(&id) {
+                continue;
+            }
+
+            let attached = nodes.iter()
+                .find(|node| **node == relationship.start || **node == relationship.end);
+
+            if let Some(node) = attached {
+                if !detach {
+                    return Err(CypherError::new(CONSTRAINT_VALIDATION_FAILED, &format!(
+                        "Cannot delete node<{}>, because it still has relationships. To delete \
+                         this node, you must first delete its relationships.", node)));
+                }
+
+                relationships.insert(id);
+            }
+        }
+
+        for id in relationships {
+            if self.store.relationships.remove(&id).is_some() {
+                self.stats.relationship_deleted += 1;
+            }
+        }
+
+        for id in nodes {
+            if self.store.nodes.remove(&id).is_some() {
+                self.stats.nodes_deleted += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Projects the rows for `WITH` and `RETURN`, returning the column names and the values
+    fn project(&self, rows: Vec<Row>, projection: &Projection, variables: &BTreeSet<String>)
+        -> Result<(Vec<String>, Vec<Vec<Value>>), CypherError>
+    {
+        let mut items = Vec::new();
+        if projection.star {
+            for variable in variables.iter().filter(|v| !v.starts_with(ANONYMOUS)) {
+                items.push((Expr::Variable(variable.clone()), variable.clone()));
+            }
+        }
+        items.extend(projection.items.iter().cloned());
+
+        if items.is_empty() {
+            return Err(CypherError::new(SYNTAX_ERROR, "RETURN * is not allowed when there are \
+                                                       no variables in scope"));
+        }
+
+        let names: Vec<String> = items.iter().map(|item| item.1.clone()).collect();
+        let aggregating = items.iter().any(|item| contains_aggregation(&item.0));
+
+        // Values of each output row, with the row used to evaluate ORDER BY
+        let mut projected: Vec<(Vec<Value>, Row)> = Vec::new();
+
+        if aggregating {
+            let keys: Vec<usize> = (0..items.len())
+                .filter(|&index| !contains_aggregation(&items[index].0))
+                .collect();
+
+            let mut groups: Vec<(Vec<Value>, Vec<Row>)> = Vec::new();
+            for row in rows {
+                let mut key = Vec::with_capacity(keys.len());
+                for &index in &keys {
+                    key.push(self.eval(&items[index].0, &row)?);
+                }
+
+                match groups.iter().position(|group| group.0 == key) {
+                    Some(position) => groups[position].1.push(row),
+                    None => groups.push((key, vec![row])),
+                }
+            }
+
+            if groups.is_empty() && keys.is_empty() {
+                groups.push((Vec::new(), Vec::new()));
+            }
+
+            for (key, rows) in groups {
+                let scope = rows.first().cloned().unwrap_or(Row::new());
+                let mut values = Vec::with_capacity(items.len());
+                let mut key = key.into_iter();
+
+                for item in &items {
+                    if contains_aggregation(&item.0) {
+                        let expression = self.aggregate(&item.0, &rows)?;
+                        values.push(self.eval(&expression, &scope)?);
+                    } else {
+                        values.push(key.next().unwrap_or(Value::Null));
+                    }
+                }
+
+                projected.push((values, scope));
+            }
+        } else {
+            for row in rows {
+                let mut values = Vec::with_capacity(items.len());
+                for item in &items {
+                    values.push(self.eval(&item.0, &row)?);
+                }
+                projected.push((values, row));
+            }
+        }
+
+        if projection.distinct {
+            let mut distinct: Vec<(Vec<Value>, Row)> = Vec::new();
+            for entry in projected {
+                if !distinct.iter().any(|existing| existing.0 == entry.0) {
+                    distinct.push(entry);
+                }
+            }
+            projected = distinct;
+        }
+
+        if !projection.order.is_empty() {
+            let mut keyed = Vec::with_capacity(projected.len());
+            for (values, mut scope) in projected {
+                for (name, value) in names.iter().zip(values.iter()) {
+                    scope.insert(name.clone(), value.clone());
+                }
+
+                let mut keys = Vec::with_capacity(projection.order.len());
+                for (expression, _) in &projection.order {
+                    let key = match items.iter().position(|item| item.0 == *expression) {
+                        Some(index) => values[index].clone(),
+                        None if contains_aggregation(expression) => {
+                            return Err(CypherError::new(SYNTAX_ERROR, "Aggregations in ORDER BY \
+                                                                       must also be returned"));
+                        },
+                        None => self.eval(expression, &scope)?,
+                    };
+                    keys.push(key);
+                }
+
+                keyed.push((keys, values));
+            }
+
+            keyed.sort_by(|a, b| {
+                for (index, &(_, ascending)) in projection.order.iter().enumerate() {
+                    let ordering = value::order(&a.0[index], &b.0[index]);
+                    if ordering != Ordering::Equal {
+                        return if ascending { ordering } else { ordering.reverse() };
+                    }
+                }
+                Ordering::Equal
+            });
+
+            projected = keyed.into_iter().map(|(_, values)| (values, Row::new())).collect();
+        }
+
+        let mut values: Vec<Vec<Value>> = projected.into_iter().map(|entry| entry.0).collect();
+
+        if let Some(ref skip) = projection.skip {
+            let skip = integer(&self.eval(skip, &Row::new())?, "SKIP")?;
+            if skip < 0 {
+                return Err(CypherError::new(SYNTAX_ERROR, "SKIP must not be negative"));
+            }
+            values = values.into_iter().skip(skip as usize).collect();
+        }
+
+        if let Some(ref limit) = projection.limit {
+            let limit = integer(&self.eval(limit, &Row::new())?, "LIMIT")?;
+            if limit < 0 {
+                return Err(CypherError::new(SYNTAX_ERROR, "LIMIT must not be negative"));
+            }
+            values.truncate(limit as usize);
+        }
+
+        Ok((names, values))
+    }
+
+    /// Replaces the aggregations in the expression with their values over the rows
+    fn aggregate(&self, expression: &Expr, rows: &[Row]) -> Result<Expr, CypherError> {
+        let aggregate = |e: &Expr| self.aggregate(e, rows).map(Box::new);
+
+        Ok(match *expression {
+            Expr::CountStar => Expr::Literal(Value::Int(rows.len() as i64)),
+            Expr::Call(ref name, distinct, ref arguments) if is_aggregation(name) => {
+                if arguments.len() != 1 {
+                    return Err(CypherError::new(SYNTAX_ERROR, &format!(
+                        "Function {}() takes exactly one argument", name)));
+                }
+
+                if contains_aggregation(&arguments[0]) {
+                    return Err(CypherError::new(SYNTAX_ERROR, "Can't use aggregate functions \
+                                                               inside of aggregate functions"));
+                }
+
+                let mut values = Vec::new();
+                for row in rows {
+                    let value = self.eval(&arguments[0], row)?;
+                    if !(value.is_null() || distinct && values.contains(&value)) {
+                        values.push(value);
+                    }
+                }
+
+                Expr::Literal(aggregation(name, values)?)
+            },
+            Expr::Call(ref name, distinct, ref arguments) => {
+                let mut aggregated = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    aggregated.push(self.aggregate(argument, rows)?);
+                }
+                Expr::Call(name.clone(), distinct, aggregated)
+            },
+            Expr::Literal(_) | Expr::Parameter(_) | Expr::Variable(_) => expression.clone(),
+            Expr::Property(ref e, ref key) => Expr::Property(aggregate(e)?, key.clone()),
+            Expr::HasLabels(ref e, ref labels) => {
+                Expr::HasLabels(aggregate(e)?, labels.clone())
+            },
+            Expr::Not(ref e) => Expr::Not(aggregate(e)?),
+            Expr::Negate(ref e) => Expr::Negate(aggregate(e)?),
+            Expr::IsNull(ref e, negated) => Expr::IsNull(aggregate(e)?, negated),
+            Expr::Index(ref a, ref b) => Expr::Index(aggregate(a)?, aggregate(b)?),
+            Expr::And(ref a, ref b) => Expr::And(aggregate(a)?, aggregate(b)?),
+            Expr::Or(ref a, ref b) => Expr::Or(aggregate(a)?, aggregate(b)?),
+            Expr::Binary(op, ref a, ref b) => {
+                Expr::Binary(op, aggregate(a)?, aggregate(b)?)
+            },
+            Expr::List(ref values) => {
+                let mut aggregated = Vec::with_capacity(values.len());
+                for value in values {
+                    aggregated.push(self.aggregate(value, rows)?);
+                }
+                Expr::List(aggregated)
+            },
+            Expr::Map(ref entries) => {
+                let mut aggregated = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    aggregated.push((key.clone(), self.aggregate(value, rows)?));
+                }
+                Expr::Map(aggregated)
+            },
+        })
+    }
+
+    fn is_true(&self, expression: &Expr, row: &Row) -> Result<bool, CypherError> {
+        Ok(truth(&self.eval(expression, row)?)? == Some(true))
+    }
+
+    fn eval(&self, expression: &Expr, row: &Row) -> Result<Value, CypherError> {
+        Ok(match *expression {
+            Expr::Literal(ref value) => value.clone(),
+            Expr::Parameter(ref name) => match self.parameters.get(name) {
+                Some(value) => value.clone(),
+                None => {
+                    return Err(CypherError::new(PARAMETER_MISSING,
+                                                &format!("Expected parameter(s): {}", name)));
+                },
+            },
+            Expr::Variable(ref name) => match row.get(name) {
+                Some(value) => value.clone(),
+                None => {
+                    return Err(CypherError::new(SYNTAX_ERROR,
+                                                &format!("Variable `{}` not defined", name)));
+                },
+            },
+            Expr::Property(ref target, ref key) => {
+                match self.eval(target, row)? {
+                    Value::Node(id) => self.store.nodes.get(&id)
+                        .and_then(|node| node.properties.get(key).cloned())
+                        .unwrap_or(Value::Null),
+                    Value::Relationship(id) => self.store.relationships.get(&id)
+                        .and_then(|relationship| relationship.properties.get(key).cloned())
+                        .unwrap_or(Value::Null),
+                    Value::Map(values) => values.get(key).cloned().unwrap_or(Value::Null),
+                    Value::Null => Value::Null,
+                    value => {
+                        return Err(type_error(&format!("Expected a Map, Node or Relationship \
+                                                        but was {}", value.type_name())));
+                    },
+                }
+            },
+            Expr::HasLabels(ref target, ref labels) => {
+                match self.eval(target, row)? {
+                    Value::Node(id) => Value::Bool(self.store.nodes.get(&id)
+                        .map(|node| labels.iter().all(|label| node.labels.contains(label)))
+                        .unwrap_or(false)),
+                    Value::Null => Value::Null,
+                    value => {
+                        return Err(type_error(&format!("Expected a Node but was {}",
+                                                       value.type_name())));
+                    },
+                }
+            },
+            Expr::Index(ref target, ref index) => {
+                match (self.eval(target, row)?, self.eval(index, row)?) {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::List(values), Value::Int(index)) => {
+                        let index = if index < 0 { values.len() as i64 + index } else { index };
+                        if index < 0 {
+                            Value::Null
+                        } else {
+                            values.into_iter().nth(index as usize).unwrap_or(Value::Null)
+                        }
+                    },
+                    (Value::Map(values), Value::String(key)) => {
+                        values.get(&key).cloned().unwrap_or(Value::Null)
+                    },
+                    (target, index) => {
+                        return Err(type_error(&format!("Can't index {} with {}",
+                                                       target.type_name(), index.type_name())));
+                    },
+                }
+            },
+            Expr::List(ref values) => {
+                let mut list = Vec::with_capacity(values.len());
+                for value in values {
+                    list.push(self.eval(value, row)?);
+                }
+                Value::List(list)
+            },
+            Expr::Map(ref entries) => {
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    map.insert(key.clone(), self.eval(value, row)?);
+                }
+                Value::Map(map)
+            },
+            Expr::Not(ref e) => match truth(&self.eval(e, row)?)? {
+                Some(value) => Value::Bool(!value),
+                None => Value::Null,
+            },
+            Expr::And(ref a, ref b) => {
+                let a = truth(&self.eval(a, row)?)?;
+                let b = truth(&self.eval(b, row)?)?;
+                match (a, b) {
+                    (Some(false), _) | (_, Some(false)) => Value::Bool(false),
+                    (Some(true), Some(true)) => Value::Bool(true),
+                    _ => Value::Null,
+                }
+            },
+            Expr::Or(ref a, ref b) => {
+                let a = truth(&self.eval(a, row)?)?;
+                let b = truth(&self.eval(b, row)?)?;
+                match (a, b) {
+                    (Some(true), _) | (_, Some(true)) => Value::Bool(true),
+                    (Some(false), Some(false)) => Value::Bool(false),
+                    _ => Value::Null,
+                }
+            },
+            Expr::Negate(ref e) => match self.eval(e, row)? {
+                Value::Int(value) => Value::Int(value.checked_neg().ok_or_else(overflow)?),
+                Value::Float(value) => Value::Float(-value),
+                Value::Null => Value::Null,
+                value => {
+                    return Err(type_error(&format!("Can't negate {}", value.type_name())));
+                },
+            },
+            Expr::Binary(op, ref a, ref b) => {
+                binary(op, self.eval(a, row)?, self.eval(b, row)?)?
+            },
+            Expr::IsNull(ref e, negated) => Value::Bool(self.eval(e, row)?.is_null() != negated),
+            Expr::Call(ref name, _, _) if is_aggregation(name) => {
+                return Err(CypherError::new(SYNTAX_ERROR, &format!(
+                    "Invalid use of aggregating function {}(...) in this context", name)));
+            },
+            Expr::CountStar => {
+                return Err(CypherError::new(SYNTAX_ERROR, "Invalid use of aggregating function \
+                                                           count(*) in this context"));
+            },
+            Expr::Call(ref name, _, ref arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.eval(argument, row)?);
+                }
+                self.function(name, values)?
+            },
+        })
+    }
+
+    fn function(&self, name: &str, mut arguments: Vec<Value>) -> Result<Value, CypherError> {
+        if name == "coalesce" {
+            return Ok(arguments.into_iter().find(|value| !value.is_null()).unwrap_or(Value::Null));
+        }
+
+        if name == "timestamp" {
+            let now = time::get_time();
+            return Ok(Value::Int(now.sec * 1000 + (now.nsec / 1_000_000) as i64));
+        }
+
+        if arguments.len() != 1 {
+            return Err(CypherError::new(SYNTAX_ERROR, &format!(
+                "Function {}() takes exactly one argument", name)));
+        }
+
+        let argument = arguments.pop().unwrap_or(Value::Null);
+        if name == "exists" {
+            return Ok(Value::Bool(!argument.is_null()));
+        }
+
+        if argument.is_null() {
+            return Ok(Value::Null);
+        }
+
+        let mismatch = |argument: &Value| {
+            Err(type_error(&format!("Invalid argument of type {} for {}()",
+                                    argument.type_name(), name)))
+        };
+
+        Ok(match (name, argument) {
+            ("id", Value::Node(id)) | ("id", Value::Relationship(id)) => Value::Int(id as i64),
+            ("labels", Value::Node(id)) => Value::List(self.store.nodes.get(&id)
+                .map(|node| node.labels.iter().cloned().map(Value::String).collect())
+                .unwrap_or_default()),
+            ("type", Value::Relationship(id)) => self.store.relationships.get(&id)
+                .map(|relationship| Value::String(relationship.rel_type.clone()))
+                .unwrap_or(Value::Null),
+            ("startnode", Value::Relationship(id)) => self.store.relationships.get(&id)
+                .map(|relationship| Value::Node(relationship.start))
+                .unwrap_or(Value::Null),
+            ("endnode", Value::Relationship(id)) => self.store.relationships.get(&id)
+                .map(|relationship| Value::Node(relationship.end))
+                .unwrap_or(Value::Null),
+            ("properties", value) | ("keys", value) => {
+                let properties = match value {
+                    Value::Map(values) => values,
+                    Value::Node(id) => self.store.nodes.get(&id)
+                        .map(|node| node.properties.clone())
+                        .unwrap_or_default(),
+                    Value::Relationship(id) => self.store.relationships.get(&id)
+                        .map(|relationship| relationship.properties.clone())
+                        .unwrap_or_default(),
+                    value => return mismatch(&value),
+                };
+
+                if name == "keys" {
+                    Value::List(properties.keys().cloned().map(Value::String).collect())
+                } else {
+                    Value::Map(properties)
+                }
+            },
+            ("size", Value::List(values)) => Value::Int(values.len() as i64),
+            ("size", Value::String(value)) => Value::Int(value.chars().count() as i64),
+            ("head", Value::List(values)) => values.into_iter().next().unwrap_or(Value::Null),
+            ("last", Value::List(values)) => values.into_iter().last().unwrap_or(Value::Null),
+            ("tolower", Value::String(value)) => Value::String(value.to_lowercase()),
+            ("toupper", Value::String(value)) => Value::String(value.to_uppercase()),
+            ("trim", Value::String(value)) => Value::String(value.trim().to_owned()),
+            ("tostring", Value::String(value)) => Value::String(value),
+            ("tostring", Value::Int(value)) => Value::String(value.to_string()),
+            ("tostring", Value::Float(value)) => Value::String(value.to_string()),
+            ("tostring", Value::Bool(value)) => Value::String(value.to_string()),
+            ("tointeger", Value::Int(value)) | ("toint", Value::Int(value)) => Value::Int(value),
+            ("tointeger", Value::Float(value)) | ("toint", Value::Float(value)) => {
+                Value::Int(value as i64)
+            },
+            ("tointeger", Value::String(value)) | ("toint", Value::String(value)) => {
+                match value.trim().parse::<i64>() {
+                    Ok(value) => Value::Int(value),
+                    Err(_) => value.trim().parse::<f64>()
+                        .map(|value| Value::Int(value as i64))
+                        .unwrap_or(Value::Null),
+                }
+            },
+            ("tofloat", Value::Int(value)) => Value::Float(value as f64),
+            ("tofloat", Value::Float(value)) => Value::Float(value),
+            ("tofloat", Value::String(value)) => {
+                value.trim().parse().map(Value::Float).unwrap_or(Value::Null)
+            },
+            ("abs", Value::Int(value)) => Value::Int(value.checked_abs().ok_or_else(overflow)?),
+            ("abs", Value::Float(value)) => Value::Float(value.abs()),
+            (name, argument) => {
+                let known = ["id", "labels", "type", "startnode", "endnode", "properties", "keys",
+                             "size", "head", "last", "tolower", "toupper", "trim", "tostring",
+                             "tointeger", "toint", "tofloat", "abs"];
+                if known.contains(&name) {
+                    return mismatch(&argument);
+                }
+
+                return Err(CypherError::new(SYNTAX_ERROR,
+                                            &format!("Unknown function '{}'", name)));
+            },
+        })
+    }
+}
+
+fn check_property(value: &Value) -> Result<(), CypherError> {
+    if value.is_property() {
+        Ok(())
+    } else {
+        Err(type_error(&format!("Property values can only be of primitive types or arrays \
+                                 thereof, but was {}", value.type_name())))
+    }
+}
+
+fn properties_match(properties: &BTreeMap<String, Value>, expected: &BTreeMap<String, Value>)
+    -> bool
+{
+    expected.iter().all(|(key, expected)| {
+        let value = properties.get(key).unwrap_or(&Value::Null);
+        value::equals(value, expected) == Some(true)
+    })
+}
+
+fn aggregation(name: &str, values: Vec<Value>) -> Result<Value, CypherError> {
+    Ok(match name {
+        "count" => Value::Int(values.len() as i64),
+        "collect" => Value::List(values),
+        "sum" | "avg" => {
+            let count = values.len();
+            let mut sum = Value::Int(0);
+            for value in values {
+                sum = binary(BinaryOp::Add, sum, value)?;
+                match sum {
+                    Value::Int(_) | Value::Float(_) => {},
+                    _ => return Err(type_error(&format!("{}() can only handle numbers", name))),
+                }
+            }
+
+            match (name, sum) {
+                ("sum", sum) => sum,
+                (_, _) if count == 0 => Value::Null,
+                (_, Value::Int(sum)) => Value::Float(sum as f64 / count as f64),
+                (_, Value::Float(sum)) => Value::Float(sum / count as f64),
+                (_, sum) => sum,
+            }
+        },
+        _ => {
+            let ordering = if name == "min" { Ordering::Less } else { Ordering::Greater };
+            let mut result = Value::Null;
+            for value in values {
+                if result.is_null() || value::order(&value, &result) == ordering {
+                    result = value;
+                }
+            }
+            result
+        },
+    })
+}
+
+fn binary(op: BinaryOp, a: Value, b: Value) -> Result<Value, CypherError> {
+    use super::parser::BinaryOp::*;
+
+    match op {
+        Eq => return Ok(value::equals(&a, &b).map(Value::Bool).unwrap_or(Value::Null)),
+        Ne => return Ok(value::equals(&a, &b).map(|eq| Value::Bool(!eq)).unwrap_or(Value::Null)),
+        In => {
+            return match b {
+                Value::List(values) => {
+                    let mut result = Value::Bool(false);
+                    for value in &values {
+                        match value::equals(&a, value) {
+                            Some(true) => return Ok(Value::Bool(true)),
+                            None => result = Value::Null,
+                            Some(false) => {},
+                        }
+                    }
+                    Ok(result)
+                },
+                Value::Null => Ok(Value::Null),
+                b => Err(type_error(&format!("Expected a List for IN but was {}", b.type_name()))),
+            };
+        },
+        _ => {},
+    }
+
+    if a.is_null() || b.is_null() {
+        return Ok(Value::Null);
+    }
+
+    Ok(match (op, a, b) {
+        (Lt, a, b) => value::compare(&a, &b).map(|o| Value::Bool(o == Ordering::Less))
+            .unwrap_or(Value::Null),
+        (Le, a, b) => value::compare(&a, &b).map(|o| Value::Bool(o != Ordering::Greater))
+            .unwrap_or(Value::Null),
+        (Gt, a, b) => value::compare(&a, &b).map(|o| Value::Bool(o == Ordering::Greater))
+            .unwrap_or(Value::Null),
+        (Ge, a, b) => value::compare(&a, &b).map(|o| Value::Bool(o != Ordering::Less))
+            .unwrap_or(Value::Null),
+        (StartsWith, Value::String(a), Value::String(b)) => Value::Bool(a.starts_with(&b[..])),
+        (EndsWith, Value::String(a), Value::String(b)) => Value::Bool(a.ends_with(&b[..])),
+        (Contains, Value::String(a), Value::String(b)) => Value::Bool(a.contains(&b[..])),
+        (StartsWith, _, _) | (EndsWith, _, _) | (Contains, _, _) => Value::Null,
+        (Add, Value::List(mut a), Value::List(b)) => {
+            a.extend(b);
+            Value::List(a)
+        },
+        (Add, Value::List(mut a), b) => {
+            a.push(b);
+            Value::List(a)
+        },
+        (Add, a, Value::List(b)) => {
+            let mut list = vec![a];
+            list.extend(b);
+            Value::List(list)
+        },
+        (Add, Value::String(a), Value::String(b)) => Value::String(a + &b),
+        (Add, Value::String(a), Value::Int(b)) => Value::String(format!("{}{}", a, b)),
+        (Add, Value::String(a), Value::Float(b)) => Value::String(format!("{}{}", a, b)),
+        (Add, Value::Int(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+        (Add, Value::Float(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+        (Add, Value::Int(a), Value::Int(b)) => Value::Int(a.checked_add(b).ok_or_else(overflow)?),
+        (Sub, Value::Int(a), Value::Int(b)) => Value::Int(a.checked_sub(b).ok_or_else(overflow)?),
+        (Mul, Value::Int(a), Value::Int(b)) => Value::Int(a.checked_mul(b).ok_or_else(overflow)?),
+        (Div, Value::Int(_), Value::Int(0)) | (Mod, Value::Int(_), Value::Int(0)) => {
+            return Err(CypherError::new(ARITHMETIC_ERROR, "/ by zero"));
+        },
+        (Div, Value::Int(a), Value::Int(b)) => Value::Int(a.checked_div(b).ok_or_else(overflow)?),
+        (Mod, Value::Int(a), Value::Int(b)) => Value::Int(a.checked_rem(b).ok_or_else(overflow)?),
+        (op, a, b) => {
+            let (x, y) = match (&a, &b) {
+                (&Value::Int(x), &Value::Float(y)) => (x as f64, y),
+                (&Value::Float(x), &Value::Int(y)) => (x, y as f64),
+                (&Value::Float(x), &Value::Float(y)) => (x, y),
+                _ => {
+                    return Err(type_error(&format!("Unsupported operation for {} and {}",
+                                                   a.type_name(), b.type_name())));
+                },
+            };
+
+            Value::Float(match op {
+                Add => x + y,
+                Sub => x - y,
+                Mul => x * y,
+                Div => x / y,
+                _ => x % y,
+            })
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use super::super::parser::parse;
+    use super::super::value::Value;
+    use super::*;
+
+    fn run(store: &mut Store, text: &str) -> Output {
+        execute(store, &parse(text).unwrap(), &BTreeMap::new()).unwrap()
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_owned())
+    }
+
+    fn people() -> Store {
+        let mut store = Store::default();
+        run(&mut store, "CREATE (a:Person {name: 'Alice', age: 30})-[:KNOWS {since: 2010}]->\
+                         (b:Person {name: 'Bob', age: 25}), (c:Person {name: 'Carol'}), \
+                         (b)-[:KNOWS]->(c)");
+        store
+    }
+
+    #[test]
+    fn match_patterns() {
+        let mut store = people();
+        assert_eq!(3, store.nodes.len());
+        assert_eq!(2, store.relationships.len());
+
+        let output = run(&mut store, "MATCH (a:Person)-[r:KNOWS]->(b) WHERE r.since > 2000 \
+                                      RETURN a.name, b.name");
+        assert_eq!(vec!["a.name".to_owned(), "b.name".to_owned()], output.columns);
+        assert_eq!(vec![vec![string("Alice"), string("Bob")]], output.rows);
+
+        let output = run(&mut store, "MATCH (n:Person {name: 'Bob'})--(m) RETURN m.name ORDER BY m.name");
+        assert_eq!(vec![vec![string("Alice")], vec![string("Carol")]], output.rows);
+
+        let output = run(&mut store, "MATCH (n:Person) OPTIONAL MATCH (n)-[:KNOWS]->(m) \
+                                      RETURN n.name AS name, m.name AS friend ORDER BY name DESC");
+        assert_eq!(vec![vec![string("Carol"), Value::Null],
+                        vec![string("Bob"), string("Carol")],
+                        vec![string("Alice"), string("Bob")]], output.rows);
+
+        let output = run(&mut store, "MATCH (n) WHERE n.age IS NULL OR n.name STARTS WITH 'A' \
+                                      RETURN n.name ORDER BY n.name");
+        assert_eq!(vec![vec![string("Alice")], vec![string("Carol")]], output.rows);
+    }
+
+    #[test]
+    fn aggregations() {
+        let mut store = people();
+
+        let output = run(&mut store, "MATCH (n:Person) RETURN count(*), count(n.age), \
+                                      sum(n.age), avg(n.age), min(n.name), max(n.age)");
+        assert_eq!(vec![vec![Value::Int(3), Value::Int(2), Value::Int(55), Value::Float(27.5),
+                             string("Alice"), Value::Int(30)]], output.rows);
+
+        let output = run(&mut store, "MATCH (a)-[:KNOWS]->(b) WITH a, collect(b.name) AS names \
+                                      RETURN a.name, size(names) ORDER BY a.name");
+        assert_eq!(vec![vec![string("Alice"), Value::Int(1)],
+                        vec![string("Bob"), Value::Int(1)]], output.rows);
+
+        let output = run(&mut store, "MATCH (n:Missing) RETURN count(n)");
+        assert_eq!(vec![vec![Value::Int(0)]], output.rows);
+
+        let output = run(&mut store, "UNWIND [1, 1, 2, null] AS x RETURN DISTINCT x SKIP 1 LIMIT 5");
+        assert_eq!(vec![vec![Value::Int(2)], vec![Value::Null]], output.rows);
+    }
+
+    #[test]
+    fn updates_and_stats() {
+        let mut store = people();
+
+        let output = run(&mut store, "MATCH (n:Person {name: 'Carol'}) \
+                                      SET n.age = 40, n:Admin REMOVE n:Person RETURN labels(n)");
+        assert_eq!(vec![vec![Value::List(vec![string("Admin")])]], output.rows);
+        assert_eq!((1, 1, 1), (output.stats.properties_set, output.stats.labels_added,
+                               output.stats.labels_removed));
+
+        let output = run(&mut store, "MERGE (n:Person {name: 'Dave'}) ON CREATE SET n.new = true \
+                                      ON MATCH SET n.new = false RETURN n.new");
+        assert_eq!(vec![vec![Value::Bool(true)]], output.rows);
+        let output = run(&mut store, "MERGE (n:Person {name: 'Dave'}) ON CREATE SET n.new = true \
+                                      ON MATCH SET n.new = false RETURN n.new");
+        assert_eq!(vec![vec![Value::Bool(false)]], output.rows);
+        assert_eq!(0, output.stats.nodes_created);
+
+        let query = parse("MATCH (n:Person {name: 'Bob'}) DELETE n").unwrap();
+        let error = execute(&mut store, &query, &BTreeMap::new()).unwrap_err();
+        assert_eq!(::memory::CONSTRAINT_VALIDATION_FAILED, error.code);
+
+        let output = run(&mut store, "MATCH (n:Person {name: 'Bob'}) DETACH DELETE n");
+        assert_eq!((1, 2), (output.stats.nodes_deleted, output.stats.relationship_deleted));
+        assert!(store.relationships.is_empty());
+    }
+
+    #[test]
+    fn parameters_and_errors() {
+        let mut store = Store::default();
+        let mut parameters = BTreeMap::new();
+        parameters.insert("names".to_owned(), Value::List(vec![string("a"), string("b")]));
+        parameters.insert("min".to_owned(), Value::Int(i64::MIN));
+
+        let query = parse("UNWIND {names} AS name CREATE (n:Item {name: name}) \
+                           RETURN toUpper(n.name) + '!' AS shout").unwrap();
+        let output = execute(&mut store, &query, &parameters).unwrap();
+        assert_eq!(vec![vec![string("A!")], vec![string("B!")]], output.rows);
+        assert_eq!(2, output.stats.nodes_created);
+
+        let errors = [("RETURN $missing", ::memory::PARAMETER_MISSING),
+                      ("RETURN 1 / 0", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN -{min}", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN abs({min})", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN {min} / -1", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN {min} % -1", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN {min} - 1", ::memory::ARITHMETIC_ERROR),
+                      ("RETURN missing", ::memory::SYNTAX_ERROR),
+                      ("CREATE (n {map: {a: 1}})", ::memory::TYPE_ERROR),
+                      ("RETURN unknown(1)", ::memory::SYNTAX_ERROR)];
+
+        for &(text, code) in &errors {
+            let error = execute(&mut store, &parse(text).unwrap(), &parameters).unwrap_err();
+            assert_eq!(code, error.code);
+        }
+    }
+}
@@ -0,0 +1,596 @@
+//! In-memory graph that runs a subset of cypher, for tests that should not need a server
+//!
+//! Only available with the `testing` feature.
+//!
+//! `MemoryGraph` is a `Transport` that executes the statements itself instead of sending them to
+//! a server, and answers in the same shapes as the transaction endpoint, so a `GraphClient`
+//! returned by `MemoryGraph::connect` can be handed to code built on `Cypher::exec` and
+//! transactions. No socket is opened.
+//!
+//! The supported subset is:
+//!
+//! - `MATCH` and `OPTIONAL MATCH` with labels, property maps, relationship types and directions,
+//!   and `WHERE`
+//! - `CREATE`, `MERGE` with `ON CREATE SET` and `ON MATCH SET`
+//! - `SET`, `REMOVE`, `DELETE` and `DETACH DELETE`
+//! - `UNWIND`, `WITH` and `RETURN`, with `DISTINCT`, `ORDER BY`, `SKIP` and `LIMIT`
+//! - the aggregations `count`, `sum`, `avg`, `min`, `max` and `collect`
+//! - the functions `id`, `labels`, `type`, `keys`, `properties`, `exists`, `coalesce`, `size`,
+//!   `head`, `last`, `startNode`, `endNode`, `toLower`, `toUpper`, `trim`, `toString`,
+//!   `toInteger`, `toFloat`, `abs` and `timestamp`
+//!
+//! Anything else, like indexes, constraints, procedures or variable length relationships, fails
+//! with a `Neo.ClientError.Statement.SyntaxError` saying it is not supported.
+//!
+//! Statements sent without a transaction are atomic. An open transaction works on a copy of the
+//! graph, which replaces the graph when the transaction commits. A transaction that made changes
+//! fails to commit with a `Neo.TransientError.Transaction.Outdated` error if the graph was
+//! changed since it began, instead of discarding those changes. As in neo4j, a statement that
+//! fails rolls back the transaction it was sent in. Clones of a `MemoryGraph` share the same
+//! graph.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusted_cypher::Statement;
+//! use rusted_cypher::memory::MemoryGraph;
+//!
+//! let memory = MemoryGraph::new();
+//! let graph = memory.connect().unwrap();
+//!
+//! let statement = Statement::new("CREATE (:LANG {name: {name}, level: 'low'})")
+//!     .with_param("name", "Rust".to_owned());
+//! graph.cypher().exec::<()>(statement).unwrap();
+//!
+//! let names: Vec<(String,)> = graph.cypher()
+//!     .exec("MATCH (n:LANG) WHERE n.level = 'low' RETURN n.name".into())
+//!     .unwrap();
+//!
+//! assert_eq!(vec![("Rust".to_owned(),)], names);
+//! assert_eq!(1, memory.node_count());
+//! ```
+
+mod engine;
+mod parser;
+mod value;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use hyper::method::Method;
+use rustc_serialize::json::{Json, ToJson};
+use time;
+
+use ::error::GraphError;
+use ::graph::GraphClient;
+use ::transport::{Transport, TransportRequest, TransportResponse};
+
+use self::engine::{Output, Stats, Store};
+use self::value::Value;
+
+const BASE_URL: &'static str = "http://memory/db/data";
+const VERSION: &'static str = "3.5.0";
+
+pub const SYNTAX_ERROR: &'static str = "Neo.ClientError.Statement.SyntaxError";
+pub const PARAMETER_MISSING: &'static str = "Neo.ClientError.Statement.ParameterMissing";
+pub const TYPE_ERROR: &'static str = "Neo.ClientError.Statement.TypeError";
+pub const ARITHMETIC_ERROR: &'static str = "Neo.ClientError.Statement.ArithmeticError";
+pub const CONSTRAINT_VALIDATION_FAILED: &'static str =
+    "Neo.ClientError.Schema.ConstraintValidationFailed";
+pub const TRANSACTION_OUTDATED: &'static str = "Neo.TransientError.Transaction.Outdated";
+
+/// Error of a statement executed by the `MemoryGraph`, sent back as a neo4j error
+#[derive(Clone, Debug, PartialEq)]
+pub struct CypherError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl CypherError {
+    pub fn new(code: &'static str, message: &str) -> Self {
+        CypherError {
+            code: code,
+            message: message.to_owned(),
+        }
+    }
+}
+
+struct State {
+    store: Store,
+    /// Incremented each time `store` is changed
+    version: u64,
+    transactions: BTreeMap<u64, OpenTransaction>,
+    next_transaction: u64,
+}
+
+/// The copy of the graph an open transaction works on
+struct OpenTransaction {
+    store: Store,
+    /// The version of the graph when the transaction began
+    version: u64,
+    changed: bool,
+}
+
+/// Graph kept in memory, used as the transport of a `GraphClient`
+#[derive(Clone)]
+pub struct MemoryGraph {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MemoryGraph {
+    fn default() -> Self {
+        MemoryGraph::new()
+    }
+}
+
+impl MemoryGraph {
+    /// Creates an empty graph
+    pub fn new() -> Self {
+        MemoryGraph {
+            state: Arc::new(Mutex::new(State {
+                store: Store::default(),
+                version: 0,
+                transactions: BTreeMap::new(),
+                next_transaction: 1,
+            })),
+        }
+    }
+
+    /// Connects a `GraphClient` to this graph
+    pub fn connect(&self) -> Result<GraphClient, GraphError> {
+        GraphClient::connect_with_transport(BASE_URL, Arc::new(self.clone()))
+    }
+
+    /// Returns the number of nodes, not counting the changes of open transactions
+    pub fn node_count(&self) -> usize {
+        self.state.lock().unwrap().store.nodes.len()
+    }
+
+    /// Returns the number of relationships, not counting the changes of open transactions
+    pub fn relationship_count(&self) -> usize {
+        self.state.lock().unwrap().store.relationships.len()
+    }
+
+    /// Returns the ids of the transactions begun and not yet committed or rolled back
+    pub fn open_transactions(&self) -> Vec<u64> {
+        self.state.lock().unwrap().transactions.keys().cloned().collect()
+    }
+
+    /// Removes all nodes and relationships
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.store = Store::default();
+        state.version += 1;
+    }
+}
+
+impl Transport for MemoryGraph {
+    fn send(&self, request: &TransportRequest) -> Result<TransportResponse, GraphError> {
+        let body = if request.body.trim().is_empty() {
+            Json::Null
+        } else {
+            Json::from_str(&request.body)?
+        };
+
+        let (status, location, body) = self.state.lock().unwrap()
+            .reply(&request.method, &request.url, &body);
+
+        Ok(TransportResponse {
+            status: status,
+            location: location,
+            body: body,
+        })
+    }
+}
+
+impl State {
+    fn reply(&mut self, method: &Method, url: &str, body: &Json) -> (u16, Option<String>, String) {
+        let path = match url.find("://") {
+            Some(index) => {
+                let rest = &url[index + 3..];
+                rest.find('/').map(|index| &rest[index..]).unwrap_or("")
+            },
+            None => url,
+        };
+
+        let path = path.split('?').next().unwrap_or("").trim_end_matches('/');
+        let segments: Vec<&str> = path.trim_start_matches("/db/data").split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        match segments.len() {
+            0 if *method == Method::Get => (200, None, service_root()),
+            1 if *method == Method::Post && segments[0] == "transaction" => self.begin(body),
+            2 if *method == Method::Post && segments[0] == "transaction" && segments[1] == "commit" => {
+                let mut store = self.store.clone();
+                let (results, errors, changed) = run(&mut store, body);
+                if errors.is_empty() && changed {
+                    self.store = store;
+                    self.version += 1;
+                }
+
+                (200, None, results_body(results, errors))
+            },
+            2 | 3 if segments[0] == "transaction" => {
+                let id = match segments[1].parse() {
+                    Ok(id) if self.transactions.contains_key(&id) => id,
+                    _ => {
+                        return (404, None, error_body(
+                            "Neo.ClientError.Transaction.TransactionNotFound",
+                            &format!("Unrecognized transaction id {}", segments[1])));
+                    },
+                };
+
+                match (method, segments.get(2)) {
+                    (&Method::Post, None) => self.exec(id, body, false),
+                    (&Method::Post, Some(&"commit")) => self.exec(id, body, true),
+                    (&Method::Delete, None) => {
+                        self.transactions.remove(&id);
+                        (200, None, results_body(Vec::new(), Vec::new()))
+                    },
+                    _ => not_supported(url),
+                }
+            },
+            _ => not_supported(url),
+        }
+    }
+
+    fn begin(&mut self, body: &Json) -> (u16, Option<String>, String) {
+        let id = self.next_transaction;
+        self.next_transaction += 1;
+
+        let mut store = self.store.clone();
+        let (results, errors, changed) = run(&mut store, body);
+        if errors.is_empty() {
+            self.transactions.insert(id, OpenTransaction {
+                store: store,
+                version: self.version,
+                changed: changed,
+            });
+        }
+
+        let location = format!("{}/transaction/{}", BASE_URL, id);
+        (201, Some(location), transaction_body(id, results, errors))
+    }
+
+    /// Runs the statements in an open transaction, committing it if asked to
+    fn exec(&mut self, id: u64, body: &Json, commit: bool) -> (u16, Option<String>, String) {
+        // As in neo4j, errors roll back the transaction
+        let mut transaction = match self.transactions.remove(&id) {
+            Some(transaction) => transaction,
+            None => return (200, None, results_body(Vec::new(), Vec::new())),
+        };
+        let (results, mut errors, changed) = run(&mut transaction.store, body);
+        transaction.changed |= changed;
+
+        if commit {
+            if errors.is_empty() && transaction.changed {
+                if transaction.version == self.version {
+                    self.store = transaction.store;
+                    self.version += 1;
+                } else {
+                    errors.push(error_json(&CypherError::new(
+                        TRANSACTION_OUTDATED,
+                        "The graph was changed by another transaction since this one began")));
+                }
+            }
+            (200, None, results_body(results, errors))
+        } else {
+            if errors.is_empty() {
+                self.transactions.insert(id, transaction);
+            }
+            (200, None, transaction_body(id, results, errors))
+        }
+    }
+}
+
+/// Runs the statements of the request body, stopping at the first error
+///
+/// Returns the results, the errors and whether the statements changed the graph. The store is
+/// left partially updated by errors, so it must be a copy the caller can discard.
+fn run(store: &mut Store, body: &Json) -> (Vec<Json>, Vec<Json>, bool) {
+    let statements = body.find("statements")
+        .and_then(|statements| statements.as_array())
+        .cloned()
+        .unwrap_or(Vec::new());
+
+    let mut results = Vec::new();
+    let mut changed = false;
+    for statement in statements {
+        match run_statement(store, &statement) {
+            Ok((result, stats)) => {
+                changed |= stats != Stats::default();
+                results.push(result);
+            },
+            Err(error) => return (results, vec![error_json(&error)], changed),
+        }
+    }
+
+    (results, Vec::new(), changed)
+}
+
+fn error_json(error: &CypherError) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("code".to_owned(), error.code.to_json());
+    json.insert("message".to_owned(), error.message.to_json());
+    Json::Object(json)
+}
+
+/// Runs a statement, returning its result and the changes it made
+fn run_statement(store: &mut Store, statement: &Json) -> Result<(Json, Stats), CypherError> {
+    let text = statement.find("statement").and_then(|s| s.as_string()).unwrap_or("");
+    let include_stats = statement.find("includeStats")
+        .and_then(|include| include.as_boolean())
+        .unwrap_or(false);
+    let include_graph = statement.find("resultDataContents")
+        .and_then(|contents| contents.as_array())
+        .map(|contents| contents.iter().any(|content| content.as_string() == Some("graph")))
+        .unwrap_or(false);
+
+    // Statements without text only keep the transaction alive
+    if text.trim().is_empty() {
+        let output = Output {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            stats: Default::default(),
+        };
+        return Ok((result_json(store, output, false, false), Stats::default()));
+    }
+
+    let parameters = statement.find("parameters")
+        .and_then(|params| params.as_object())
+        .map(|params| params.iter()
+            .map(|(name, value)| (name.clone(), Value::from_json(value)))
+            .collect())
+        .unwrap_or_default();
+
+    let query = parser::parse(text)?;
+    let output = engine::execute(store, &query, &parameters)?;
+    let stats = output.stats.clone();
+
+    Ok((result_json(store, output, include_stats, include_graph), stats))
+}
+
+fn result_json(store: &Store, output: Output, include_stats: bool, include_graph: bool) -> Json {
+    let data = output.rows.iter().map(|row| {
+        let mut data = BTreeMap::new();
+        data.insert("row".to_owned(),
+                    Json::Array(row.iter().map(|value| to_json(store, value)).collect()));
+
+        if include_graph {
+            data.insert("graph".to_owned(), graph_json(store, row));
+        }
+
+        Json::Object(data)
+    }).collect();
+
+    let mut result = BTreeMap::new();
+    result.insert("columns".to_owned(), output.columns.to_json());
+    result.insert("data".to_owned(), Json::Array(data));
+
+    if include_stats {
+        let stats = output.stats;
+        let mut json = BTreeMap::new();
+        for &(name, value) in &[("nodes_created", stats.nodes_created),
+                                ("nodes_deleted", stats.nodes_deleted),
+                                ("properties_set", stats.properties_set),
+                                ("relationships_created", stats.relationships_created),
+                                ("relationship_deleted", stats.relationship_deleted),
+                                ("labels_added", stats.labels_added),
+                                ("labels_removed", stats.labels_removed),
+                                ("indexes_added", 0),
+                                ("indexes_removed", 0),
+                                ("constraints_added", 0),
+                                ("constraints_removed", 0)] {
+            json.insert(name.to_owned(), value.to_json());
+        }
+
+        let contains_updates = json.values().any(|value| value.as_u64() != Some(0));
+        json.insert("contains_updates".to_owned(), contains_updates.to_json());
+        result.insert("stats".to_owned(), Json::Object(json));
+    }
+
+    Json::Object(result)
+}
+
+/// Converts a value to the row format, where nodes and relationships are their properties
+fn to_json(store: &Store, value: &Value) -> Json {
+    match *value {
+        Value::Null => Json::Null,
+        Value::Bool(value) => Json::Boolean(value),
+        Value::Int(value) => Json::I64(value),
+        Value::Float(value) => Json::F64(value),
+        Value::String(ref value) => Json::String(value.clone()),
+        Value::List(ref values) => {
+            Json::Array(values.iter().map(|value| to_json(store, value)).collect())
+        },
+        Value::Map(ref values) => properties_json(store, values),
+        Value::Node(id) => store.nodes.get(&id)
+            .map(|node| properties_json(store, &node.properties))
+            .unwrap_or(Json::Null),
+        Value::Relationship(id) => store.relationships.get(&id)
+            .map(|relationship| properties_json(store, &relationship.properties))
+            .unwrap_or(Json::Null),
+    }
+}
+
+fn properties_json(store: &Store, properties: &BTreeMap<String, Value>) -> Json {
+    Json::Object(properties.iter()
+        .map(|(key, value)| (key.clone(), to_json(store, value)))
+        .collect())
+}
+
+/// Returns the nodes and relationships of a row in the graph format
+fn graph_json(store: &Store, row: &[Value]) -> Json {
+    fn collect(value: &Value, nodes: &mut Vec<u64>, relationships: &mut Vec<u64>) {
+        match *value {
+            Value::Node(id) if !nodes.contains(&id) => nodes.push(id),
+            Value::Relationship(id) if !relationships.contains(&id) => relationships.push(id),
+            Value::List(ref values) => {
+                for value in values {
+                    collect(value, nodes, relationships);
+                }
+            },
+            Value::Map(ref values) => {
+                for value in values.values() {
+                    collect(value, nodes, relationships);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    let mut nodes = Vec::new();
+    let mut relationships = Vec::new();
+    for value in row {
+        collect(value, &mut nodes, &mut relationships);
+    }
+
+    let nodes = nodes.iter().filter_map(|id| store.nodes.get(id).map(|node| {
+        let mut json = BTreeMap::new();
+        json.insert("id".to_owned(), id.to_string().to_json());
+        json.insert("labels".to_owned(), node.labels.to_json());
+        json.insert("properties".to_owned(), properties_json(store, &node.properties));
+        Json::Object(json)
+    })).collect();
+
+    let relationships = relationships.iter().filter_map(|id| {
+        store.relationships.get(id).map(|relationship| {
+            let mut json = BTreeMap::new();
+            json.insert("id".to_owned(), id.to_string().to_json());
+            json.insert("type".to_owned(), relationship.rel_type.to_json());
+            json.insert("startNode".to_owned(), relationship.start.to_string().to_json());
+            json.insert("endNode".to_owned(), relationship.end.to_string().to_json());
+            json.insert("properties".to_owned(),
+                        properties_json(store, &relationship.properties));
+            Json::Object(json)
+        })
+    }).collect();
+
+    let mut graph = BTreeMap::new();
+    graph.insert("nodes".to_owned(), Json::Array(nodes));
+    graph.insert("relationships".to_owned(), Json::Array(relationships));
+    Json::Object(graph)
+}
+
+fn service_root() -> String {
+    let mut root = BTreeMap::new();
+    for &(key, path) in &[("node", "/node"),
+                          ("node_index", "/index/node"),
+                          ("relationship_index", "/index/relationship"),
+                          ("extensions_info", "/ext"),
+                          ("relationship_types", "/relationship/types"),
+                          ("batch", "/batch"),
+                          ("cypher", "/cypher"),
+                          ("indexes", "/schema/index"),
+                          ("constraints", "/schema/constraint"),
+                          ("transaction", "/transaction"),
+                          ("node_labels", "/labels")] {
+        root.insert(key.to_owned(), format!("{}{}", BASE_URL, path).to_json());
+    }
+    root.insert("neo4j_version".to_owned(), VERSION.to_json());
+
+    Json::Object(root).to_string()
+}
+
+fn transaction_body(id: u64, results: Vec<Json>, errors: Vec<Json>) -> String {
+    let expires = time::now_utc() + time::Duration::seconds(60);
+
+    let mut transaction = BTreeMap::new();
+    transaction.insert("expires".to_owned(), expires.rfc822z().to_string().to_json());
+
+    let mut body = BTreeMap::new();
+    body.insert("commit".to_owned(), format!("{}/transaction/{}/commit", BASE_URL, id).to_json());
+    body.insert("transaction".to_owned(), Json::Object(transaction));
+    body.insert("results".to_owned(), Json::Array(results));
+    body.insert("errors".to_owned(), Json::Array(errors));
+
+    Json::Object(body).to_string()
+}
+
+fn results_body(results: Vec<Json>, errors: Vec<Json>) -> String {
+    let mut body = BTreeMap::new();
+    body.insert("results".to_owned(), Json::Array(results));
+    body.insert("errors".to_owned(), Json::Array(errors));
+    Json::Object(body).to_string()
+}
+
+fn error_body(code: &str, message: &str) -> String {
+    let mut error = BTreeMap::new();
+    error.insert("code".to_owned(), code.to_json());
+    error.insert("message".to_owned(), message.to_json());
+    results_body(Vec::new(), vec![Json::Object(error)])
+}
+
+fn not_supported(url: &str) -> (u16, Option<String>, String) {
+    (404, None, error_body("Neo.ClientError.Request.Invalid",
+                           &format!("{} is not supported by the in-memory graph", url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transactions_work_on_a_copy() {
+        let memory = MemoryGraph::new();
+        let graph = memory.connect().unwrap();
+
+        let (transaction, _) = graph.cypher().transaction()
+            .begin::<()>(Some("CREATE (:A)".into()))
+            .unwrap();
+        assert_eq!(0, memory.node_count());
+        assert_eq!(vec![1], memory.open_transactions());
+
+        transaction.commit::<()>(Some("CREATE (:B)".into())).unwrap();
+        assert_eq!(2, memory.node_count());
+        assert!(memory.open_transactions().is_empty());
+
+        let (transaction, _) = graph.cypher().transaction()
+            .begin::<()>(Some("MATCH (n) DETACH DELETE n".into()))
+            .unwrap();
+        transaction.rollback().unwrap();
+        assert_eq!(2, memory.node_count());
+    }
+
+    #[test]
+    fn commit_fails_when_the_graph_changed() {
+        let memory = MemoryGraph::new();
+        let graph = memory.connect().unwrap();
+
+        let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+        transaction.exec::<()>("CREATE (:A)".into()).unwrap();
+        graph.cypher().exec::<()>("CREATE (:B)".into()).unwrap();
+
+        let error = transaction.commit::<()>(None).unwrap_err();
+        assert_eq!(TRANSACTION_OUTDATED, error.neo4j_errors().unwrap()[0].code);
+        assert_eq!(1, memory.node_count());
+        assert!(memory.open_transactions().is_empty());
+
+        // Reads outside the transaction, and transactions without changes, don't conflict
+        let (mut transaction, _) = graph.cypher().transaction()
+            .begin::<()>(Some("CREATE (:A)".into()))
+            .unwrap();
+        let (mut reader, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+        reader.exec_raw("MATCH (n) RETURN n".into()).unwrap();
+        graph.cypher().exec_raw("MATCH (n:B) RETURN n".into()).unwrap();
+        transaction.exec_raw("MATCH (n:A) RETURN n".into()).unwrap();
+        transaction.commit::<()>(None).unwrap();
+        reader.commit::<()>(None).unwrap();
+        assert_eq!(2, memory.node_count());
+    }
+
+    #[test]
+    fn errors_roll_back() {
+        let memory = MemoryGraph::new();
+        let graph = memory.connect().unwrap();
+
+        let error = graph.cypher().exec::<()>("CREATE (:A) WITH 1 AS x RETURN x / 0".into())
+            .unwrap_err();
+        assert_eq!(ARITHMETIC_ERROR, error.neo4j_errors().unwrap()[0].code);
+        assert_eq!(0, memory.node_count());
+
+        let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+        assert!(transaction.exec::<()>("RETURN {missing}".into()).is_err());
+        assert!(memory.open_transactions().is_empty());
+    }
+}
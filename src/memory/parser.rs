@@ -0,0 +1,1080 @@
+use super::{CypherError, SYNTAX_ERROR};
+use super::value::Value;
+
+/// The statements of a query, in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Clause {
+    Match { optional: bool, patterns: Vec<Pattern>, filter: Option<Expr> },
+    Create(Vec<Pattern>),
+    Merge { pattern: Pattern, on_create: Vec<SetItem>, on_match: Vec<SetItem> },
+    Set(Vec<SetItem>),
+    Remove(Vec<RemoveItem>),
+    Delete { detach: bool, expressions: Vec<Expr> },
+    Unwind { expression: Expr, variable: String },
+    With(Projection),
+    Return(Projection),
+    /// `CALL dbms.setTXMetaData`, which has no effect in memory
+    SetMetadata(Expr),
+}
+
+/// A chain of nodes and relationships, like `(a)-[:KNOWS]->(b)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub start: NodePattern,
+    pub steps: Vec<(RelPattern, NodePattern)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePattern {
+    pub variable: String,
+    pub labels: Vec<String>,
+    pub properties: Option<Expr>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelPattern {
+    pub variable: String,
+    pub types: Vec<String>,
+    pub properties: Option<Expr>,
+    pub direction: Direction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SetItem {
+    Property(String, String, Expr),
+    Replace(String, Expr),
+    Merge(String, Expr),
+    Labels(String, Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoveItem {
+    Property(String, String),
+    Labels(String, Vec<String>),
+}
+
+/// The items of `WITH` and `RETURN`, with their modifiers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    pub distinct: bool,
+    pub star: bool,
+    /// Expressions with their column names
+    pub items: Vec<(Expr, String)>,
+    /// Expressions with `true` for ascending order
+    pub order: Vec<(Expr, bool)>,
+    pub skip: Option<Expr>,
+    pub limit: Option<Expr>,
+    /// The `WHERE` following `WITH`
+    pub filter: Option<Expr>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    In,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Parameter(String),
+    Variable(String),
+    Property(Box<Expr>, String),
+    HasLabels(Box<Expr>, Vec<String>),
+    Index(Box<Expr>, Box<Expr>),
+    List(Vec<Expr>),
+    Map(Vec<(String, Expr)>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    IsNull(Box<Expr>, bool),
+    /// Function call, with the name in lowercase and `true` for `DISTINCT` arguments
+    Call(String, bool, Vec<Expr>),
+    CountStar,
+}
+
+/// Variables of anonymous pattern elements start with a space, so they never clash
+pub const ANONYMOUS: &'static str = " anonymous";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Quoted(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Parameter(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &'static [&'static str] = &[
+    "<>", "<=", ">=", "+=", "(", ")", "[", "]", "{", "}", ",", ":", ";", ".", "|", "=", "<", ">",
+    "+", "-", "*", "/", "%",
+];
+
+/// Parses a statement into a `Query`
+pub fn parse(text: &str) -> Result<Query, CypherError> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser {
+        text: text,
+        tokens: tokens,
+        position: 0,
+        anonymous: 0,
+    };
+
+    parser.query()
+}
+
+fn syntax_error(message: &str, offset: usize) -> CypherError {
+    CypherError::new(SYNTAX_ERROR, &format!("{} (offset: {})", message, offset))
+}
+
+fn unsupported(feature: &str) -> CypherError {
+    CypherError::new(SYNTAX_ERROR, &format!("{} is not supported by the in-memory graph", feature))
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize, usize)>, CypherError> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let offset = |index: usize| chars.get(index).map(|&(offset, _)| offset).unwrap_or(text.len());
+
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let (start, c) = chars[index];
+
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if c == '/' && chars.get(index + 1).map(|&(_, c)| c) == Some('/') {
+            while index < chars.len() && chars[index].1 != '\n' {
+                index += 1;
+            }
+            continue;
+        }
+
+        let token = if c.is_alphabetic() || c == '_' {
+            let end = chars[index..].iter()
+                .position(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+                .map(|length| index + length)
+                .unwrap_or(chars.len());
+            let ident = text[start..offset(end)].to_owned();
+            index = end;
+            Token::Ident(ident)
+        } else if c.is_ascii_digit() {
+            let mut end = index;
+            while end < chars.len() && chars[end].1.is_ascii_digit() {
+                end += 1;
+            }
+
+            let mut float = false;
+            if end + 1 < chars.len() && chars[end].1 == '.' && chars[end + 1].1.is_ascii_digit() {
+                float = true;
+                end += 1;
+                while end < chars.len() && chars[end].1.is_ascii_digit() {
+                    end += 1;
+                }
+            }
+
+            if end < chars.len() && (chars[end].1 == 'e' || chars[end].1 == 'E') {
+                let mut exponent = end + 1;
+                if exponent < chars.len() && (chars[exponent].1 == '-' || chars[exponent].1 == '+') {
+                    exponent += 1;
+                }
+                if exponent < chars.len() && chars[exponent].1.is_ascii_digit() {
+                    float = true;
+                    end = exponent;
+                    while end < chars.len() && chars[end].1.is_ascii_digit() {
+                        end += 1;
+                    }
+                }
+            }
+
+            let number = &text[start..offset(end)];
+            index = end;
+            if float {
+                match number.parse() {
+                    Ok(value) => Token::Float(value),
+                    Err(_) => return Err(syntax_error("Invalid number", start)),
+                }
+            } else {
+                match number.parse() {
+                    Ok(value) => Token::Int(value),
+                    Err(_) => return Err(syntax_error("Integer is too large", start)),
+                }
+            }
+        } else if c == '\'' || c == '"' {
+            let mut value = String::new();
+            let mut end = index + 1;
+            loop {
+                match chars.get(end).map(|&(_, c)| c) {
+                    None => return Err(syntax_error("Unterminated string", start)),
+                    Some(ch) if ch == c => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(end + 1).map(|&(_, c)| c) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some(other) => other,
+                            None => return Err(syntax_error("Unterminated string", start)),
+                        };
+                        value.push(escaped);
+                        end += 2;
+                    },
+                    Some(other) => {
+                        value.push(other);
+                        end += 1;
+                    },
+                }
+            }
+            index = end + 1;
+            Token::Str(value)
+        } else if c == '`' {
+            let end = match chars[index + 1..].iter().position(|&(_, c)| c == '`') {
+                Some(length) => index + 1 + length,
+                None => return Err(syntax_error("Unterminated identifier", start)),
+            };
+            let ident = text[offset(index + 1)..offset(end)].to_owned();
+            index = end + 1;
+            Token::Quoted(ident)
+        } else if c == '$' {
+            let end = chars[index + 1..].iter()
+                .position(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+                .map(|length| index + 1 + length)
+                .unwrap_or(chars.len());
+            if end == index + 1 {
+                return Err(syntax_error("Invalid parameter", start));
+            }
+            let name = text[offset(index + 1)..offset(end)].to_owned();
+            index = end;
+            Token::Parameter(name)
+        } else {
+            match SYMBOLS.iter().find(|symbol| text[start..].starts_with(*symbol)) {
+                Some(symbol) => {
+                    index += symbol.len();
+                    Token::Symbol(symbol)
+                },
+                None => return Err(syntax_error(&format!("Invalid input '{}'", c), start)),
+            }
+        };
+
+        tokens.push((token, start, offset(index)));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token, usize, usize)>,
+    position: usize,
+    anonymous: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, distance: usize) -> Option<&Token> {
+        self.tokens.get(self.position + distance).map(|(token, _, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.position).map(|&(_, start, _)| start).unwrap_or(self.text.len())
+    }
+
+    fn previous_end(&self) -> usize {
+        match self.position {
+            0 => 0,
+            position => self.tokens[position - 1].2,
+        }
+    }
+
+    fn error<T>(&self) -> Result<T, CypherError> {
+        let message = match self.tokens.get(self.position) {
+            Some(&(_, start, end)) => format!("Invalid input '{}'", &self.text[start..end]),
+            None => "Unexpected end of input".to_owned(),
+        };
+
+        Err(syntax_error(&message, self.offset()))
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.is_symbol_at(0, symbol)
+    }
+
+    fn is_symbol_at(&self, distance: usize, symbol: &str) -> bool {
+        match self.peek_at(distance) {
+            Some(&Token::Symbol(s)) => s == symbol,
+            _ => false,
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if self.is_symbol(symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), CypherError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            self.error()
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.is_keyword_at(0, keyword)
+    }
+
+    fn is_keyword_at(&self, distance: usize, keyword: &str) -> bool {
+        match self.peek_at(distance) {
+            Some(Token::Ident(ident)) => ident.to_uppercase() == keyword,
+            _ => false,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), CypherError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error()
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, CypherError> {
+        let ident = match self.peek() {
+            Some(&Token::Ident(ref ident)) | Some(&Token::Quoted(ref ident)) => ident.clone(),
+            _ => return self.error(),
+        };
+
+        self.position += 1;
+        Ok(ident)
+    }
+
+    fn is_ident(&self) -> bool {
+        matches!(self.peek(), Some(&Token::Ident(_)) | Some(&Token::Quoted(_)))
+    }
+
+    fn anonymous(&mut self) -> String {
+        self.anonymous += 1;
+        format!("{}{}", ANONYMOUS, self.anonymous)
+    }
+
+    fn query(&mut self) -> Result<Query, CypherError> {
+        let mut clauses = Vec::new();
+
+        while self.peek().is_some() && !self.is_symbol(";") {
+            if let Some(&Clause::Return(_)) = clauses.last() {
+                return self.error();
+            }
+
+            clauses.push(self.clause()?);
+        }
+
+        self.eat_symbol(";");
+        if self.peek().is_some() {
+            return Err(unsupported("Multiple statements in one request"));
+        }
+
+        match clauses.last() {
+            None => return Err(syntax_error("Unexpected end of input", self.text.len())),
+            Some(&Clause::Match { .. }) | Some(&Clause::With(_)) | Some(&Clause::Unwind { .. }) => {
+                return Err(syntax_error("Query cannot conclude with MATCH, WITH or UNWIND \
+                                         (must be RETURN or an update clause)",
+                                        self.text.len()));
+            },
+            _ => {},
+        }
+
+        Ok(Query { clauses: clauses })
+    }
+
+    fn clause(&mut self) -> Result<Clause, CypherError> {
+        if self.eat_keyword("MATCH") {
+            self.match_clause(false)
+        } else if self.eat_keyword("OPTIONAL") {
+            self.expect_keyword("MATCH")?;
+            self.match_clause(true)
+        } else if self.eat_keyword("CREATE") {
+            if self.is_keyword("INDEX") || self.is_keyword("CONSTRAINT") {
+                return Err(unsupported("Schema"));
+            }
+            Ok(Clause::Create(self.patterns()?))
+        } else if self.eat_keyword("MERGE") {
+            self.merge()
+        } else if self.eat_keyword("SET") {
+            Ok(Clause::Set(self.set_items()?))
+        } else if self.eat_keyword("REMOVE") {
+            self.remove()
+        } else if self.eat_keyword("DETACH") {
+            self.expect_keyword("DELETE")?;
+            self.delete(true)
+        } else if self.eat_keyword("DELETE") {
+            self.delete(false)
+        } else if self.eat_keyword("UNWIND") {
+            let expression = self.expression()?;
+            self.expect_keyword("AS")?;
+            Ok(Clause::Unwind { expression: expression, variable: self.ident()? })
+        } else if self.eat_keyword("WITH") {
+            Ok(Clause::With(self.projection(true)?))
+        } else if self.eat_keyword("RETURN") {
+            Ok(Clause::Return(self.projection(false)?))
+        } else if self.eat_keyword("CALL") {
+            self.call()
+        } else if self.is_keyword("DROP") {
+            Err(unsupported("Schema"))
+        } else if self.is_keyword("FOREACH") || self.is_keyword("LOAD") || self.is_keyword("UNION") {
+            Err(unsupported(&self.ident().unwrap_or_default().to_uppercase()))
+        } else {
+            self.error()
+        }
+    }
+
+    fn match_clause(&mut self, optional: bool) -> Result<Clause, CypherError> {
+        let patterns = self.patterns()?;
+        let filter = if self.eat_keyword("WHERE") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        Ok(Clause::Match { optional: optional, patterns: patterns, filter: filter })
+    }
+
+    fn merge(&mut self) -> Result<Clause, CypherError> {
+        let pattern = self.pattern()?;
+        let mut on_create = Vec::new();
+        let mut on_match = Vec::new();
+
+        while self.eat_keyword("ON") {
+            if self.eat_keyword("CREATE") {
+                self.expect_keyword("SET")?;
+                on_create.extend(self.set_items()?);
+            } else if self.eat_keyword("MATCH") {
+                self.expect_keyword("SET")?;
+                on_match.extend(self.set_items()?);
+            } else {
+                return self.error();
+            }
+        }
+
+        Ok(Clause::Merge { pattern: pattern, on_create: on_create, on_match: on_match })
+    }
+
+    fn set_items(&mut self) -> Result<Vec<SetItem>, CypherError> {
+        let mut items = Vec::new();
+        loop {
+            let variable = self.ident()?;
+            let item = if self.eat_symbol(".") {
+                let key = self.ident()?;
+                self.expect_symbol("=")?;
+                SetItem::Property(variable, key, self.expression()?)
+            } else if self.is_symbol(":") {
+                SetItem::Labels(variable, self.labels()?)
+            } else if self.eat_symbol("+=") {
+                SetItem::Merge(variable, self.expression()?)
+            } else if self.eat_symbol("=") {
+                SetItem::Replace(variable, self.expression()?)
+            } else {
+                return self.error();
+            };
+
+            items.push(item);
+            if !self.eat_symbol(",") {
+                return Ok(items);
+            }
+        }
+    }
+
+    fn remove(&mut self) -> Result<Clause, CypherError> {
+        let mut items = Vec::new();
+        loop {
+            let variable = self.ident()?;
+            if self.eat_symbol(".") {
+                items.push(RemoveItem::Property(variable, self.ident()?));
+            } else if self.is_symbol(":") {
+                items.push(RemoveItem::Labels(variable, self.labels()?));
+            } else {
+                return self.error();
+            }
+
+            if !self.eat_symbol(",") {
+                return Ok(Clause::Remove(items));
+            }
+        }
+    }
+
+    fn delete(&mut self, detach: bool) -> Result<Clause, CypherError> {
+        let mut expressions = vec![self.expression()?];
+        while self.eat_symbol(",") {
+            expressions.push(self.expression()?);
+        }
+
+        Ok(Clause::Delete { detach: detach, expressions: expressions })
+    }
+
+    fn call(&mut self) -> Result<Clause, CypherError> {
+        let mut name = self.ident()?;
+        while self.eat_symbol(".") {
+            name = format!("{}.{}", name, self.ident()?);
+        }
+
+        if name.to_lowercase() != "dbms.settxmetadata" {
+            return Err(unsupported(&format!("Procedure {}", name)));
+        }
+
+        self.expect_symbol("(")?;
+        let metadata = self.expression()?;
+        self.expect_symbol(")")?;
+        Ok(Clause::SetMetadata(metadata))
+    }
+
+    fn projection(&mut self, with: bool) -> Result<Projection, CypherError> {
+        let distinct = self.eat_keyword("DISTINCT");
+        let star = self.eat_symbol("*");
+
+        let mut items = Vec::new();
+        if !star || self.eat_symbol(",") {
+            loop {
+                let start = self.offset();
+                let expression = self.expression()?;
+                let name = if self.eat_keyword("AS") {
+                    self.ident()?
+                } else {
+                    match expression {
+                        Expr::Variable(ref name) => name.clone(),
+                        _ => self.text[start..self.previous_end()].to_owned(),
+                    }
+                };
+
+                items.push((expression, name));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let mut order = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expression = self.expression()?;
+                let ascending = if self.eat_keyword("DESC") || self.eat_keyword("DESCENDING") {
+                    false
+                } else {
+                    if !self.eat_keyword("ASC") {
+                        self.eat_keyword("ASCENDING");
+                    }
+                    true
+                };
+
+                order.push((expression, ascending));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let skip = if self.eat_keyword("SKIP") { Some(self.expression()?) } else { None };
+        let limit = if self.eat_keyword("LIMIT") { Some(self.expression()?) } else { None };
+        let filter = if with && self.eat_keyword("WHERE") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        Ok(Projection {
+            distinct: distinct,
+            star: star,
+            items: items,
+            order: order,
+            skip: skip,
+            limit: limit,
+            filter: filter,
+        })
+    }
+
+    fn patterns(&mut self) -> Result<Vec<Pattern>, CypherError> {
+        let mut patterns = vec![self.pattern()?];
+        while self.eat_symbol(",") {
+            patterns.push(self.pattern()?);
+        }
+
+        Ok(patterns)
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, CypherError> {
+        if self.is_ident() && self.is_symbol_at(1, "=") {
+            return Err(unsupported("Path variables"));
+        }
+
+        let start = self.node_pattern()?;
+        let mut steps = Vec::new();
+
+        loop {
+            let incoming = if self.is_symbol("<") && self.is_symbol_at(1, "-") {
+                self.position += 2;
+                true
+            } else if self.eat_symbol("-") {
+                false
+            } else {
+                break;
+            };
+
+            let mut relationship = RelPattern {
+                variable: String::new(),
+                types: Vec::new(),
+                properties: None,
+                direction: Direction::Both,
+            };
+
+            if self.eat_symbol("[") {
+                if self.is_ident() {
+                    relationship.variable = self.ident()?;
+                }
+
+                if self.eat_symbol(":") {
+                    relationship.types.push(self.ident()?);
+                    while self.eat_symbol("|") {
+                        self.eat_symbol(":");
+                        relationship.types.push(self.ident()?);
+                    }
+                }
+
+                if self.is_symbol("*") {
+                    return Err(unsupported("Variable length relationships"));
+                }
+
+                relationship.properties = self.pattern_properties()?;
+                self.expect_symbol("]")?;
+            }
+
+            self.expect_symbol("-")?;
+            let outgoing = self.eat_symbol(">");
+
+            relationship.direction = match (incoming, outgoing) {
+                (true, false) => Direction::Incoming,
+                (false, true) => Direction::Outgoing,
+                (false, false) => Direction::Both,
+                (true, true) => return self.error(),
+            };
+
+            if relationship.variable.is_empty() {
+                relationship.variable = self.anonymous();
+            }
+
+            steps.push((relationship, self.node_pattern()?));
+        }
+
+        Ok(Pattern { start: start, steps: steps })
+    }
+
+    fn node_pattern(&mut self) -> Result<NodePattern, CypherError> {
+        self.expect_symbol("(")?;
+
+        let variable = if self.is_ident() {
+            self.ident()?
+        } else {
+            self.anonymous()
+        };
+
+        let labels = if self.is_symbol(":") { self.labels()? } else { Vec::new() };
+        let properties = self.pattern_properties()?;
+        self.expect_symbol(")")?;
+
+        Ok(NodePattern { variable: variable, labels: labels, properties: properties })
+    }
+
+    fn labels(&mut self) -> Result<Vec<String>, CypherError> {
+        let mut labels = Vec::new();
+        while self.eat_symbol(":") {
+            labels.push(self.ident()?);
+        }
+
+        Ok(labels)
+    }
+
+    fn pattern_properties(&mut self) -> Result<Option<Expr>, CypherError> {
+        match self.peek() {
+            Some(&Token::Symbol("{")) | Some(&Token::Parameter(_)) => Ok(Some(self.primary()?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, CypherError> {
+        self.or()
+    }
+
+    fn or(&mut self) -> Result<Expr, CypherError> {
+        let mut expression = self.and()?;
+        while self.eat_keyword("OR") {
+            expression = Expr::Or(Box::new(expression), Box::new(self.and()?));
+        }
+
+        if self.is_keyword("XOR") {
+            return Err(unsupported("XOR"));
+        }
+
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expr, CypherError> {
+        let mut expression = self.not()?;
+        while self.eat_keyword("AND") {
+            expression = Expr::And(Box::new(expression), Box::new(self.not()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn not(&mut self) -> Result<Expr, CypherError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, CypherError> {
+        let left = self.additive()?;
+
+        let symbol = match self.peek() {
+            Some(&Token::Symbol("=")) => Some(BinaryOp::Eq),
+            Some(&Token::Symbol("<>")) => Some(BinaryOp::Ne),
+            Some(&Token::Symbol("<")) => Some(BinaryOp::Lt),
+            Some(&Token::Symbol("<=")) => Some(BinaryOp::Le),
+            Some(&Token::Symbol(">")) => Some(BinaryOp::Gt),
+            Some(&Token::Symbol(">=")) => Some(BinaryOp::Ge),
+            _ => None,
+        };
+
+        let op = if let Some(op) = symbol {
+            self.position += 1;
+            op
+        } else if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull(Box::new(left), negated));
+        } else if self.eat_keyword("IN") {
+            BinaryOp::In
+        } else if self.eat_keyword("STARTS") {
+            self.expect_keyword("WITH")?;
+            BinaryOp::StartsWith
+        } else if self.eat_keyword("ENDS") {
+            self.expect_keyword("WITH")?;
+            BinaryOp::EndsWith
+        } else if self.eat_keyword("CONTAINS") {
+            BinaryOp::Contains
+        } else {
+            return Ok(left);
+        };
+
+        let right = self.additive()?;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn additive(&mut self) -> Result<Expr, CypherError> {
+        let mut expression = self.multiplicative()?;
+        loop {
+            let op = if self.eat_symbol("+") {
+                BinaryOp::Add
+            } else if self.eat_symbol("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(expression);
+            };
+
+            let right = self.multiplicative()?;
+            expression = Expr::Binary(op, Box::new(expression), Box::new(right));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, CypherError> {
+        let mut expression = self.unary()?;
+        loop {
+            let op = if self.eat_symbol("*") {
+                BinaryOp::Mul
+            } else if self.eat_symbol("/") {
+                BinaryOp::Div
+            } else if self.eat_symbol("%") {
+                BinaryOp::Mod
+            } else {
+                return Ok(expression);
+            };
+
+            let right = self.unary()?;
+            expression = Expr::Binary(op, Box::new(expression), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, CypherError> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+
+        self.eat_symbol("+");
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, CypherError> {
+        let mut expression = self.primary()?;
+        loop {
+            if self.eat_symbol(".") {
+                expression = Expr::Property(Box::new(expression), self.ident()?);
+            } else if self.eat_symbol("[") {
+                let index = self.expression()?;
+                if self.is_symbol(".") {
+                    return Err(unsupported("List slices"));
+                }
+                self.expect_symbol("]")?;
+                expression = Expr::Index(Box::new(expression), Box::new(index));
+            } else if self.is_symbol(":") {
+                expression = Expr::HasLabels(Box::new(expression), self.labels()?);
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CypherError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.error(),
+        };
+
+        match token {
+            Token::Int(value) => {
+                self.position += 1;
+                Ok(Expr::Literal(Value::Int(value)))
+            },
+            Token::Float(value) => {
+                self.position += 1;
+                Ok(Expr::Literal(Value::Float(value)))
+            },
+            Token::Str(value) => {
+                self.position += 1;
+                Ok(Expr::Literal(Value::String(value)))
+            },
+            Token::Parameter(name) => {
+                self.position += 1;
+                Ok(Expr::Parameter(name))
+            },
+            Token::Quoted(name) => {
+                self.position += 1;
+                Ok(Expr::Variable(name))
+            },
+            Token::Symbol("(") => {
+                self.position += 1;
+                let expression = self.expression()?;
+                self.expect_symbol(")")?;
+                Ok(expression)
+            },
+            Token::Symbol("[") => {
+                self.position += 1;
+                let mut values = Vec::new();
+                if !self.eat_symbol("]") {
+                    loop {
+                        values.push(self.expression()?);
+                        if !self.eat_symbol(",") {
+                            break;
+                        }
+                    }
+                    self.expect_symbol("]")?;
+                }
+                Ok(Expr::List(values))
+            },
+            Token::Symbol("{") => {
+                // `{name}` is the old syntax for parameters
+                if self.is_symbol_at(2, "}") {
+                    if let Some(Token::Ident(name)) = self.peek_at(1) {
+                        let name = name.clone();
+                        self.position += 3;
+                        return Ok(Expr::Parameter(name));
+                    }
+                }
+
+                self.position += 1;
+                let mut entries = Vec::new();
+                if !self.eat_symbol("}") {
+                    loop {
+                        let key = self.ident()?;
+                        self.expect_symbol(":")?;
+                        entries.push((key, self.expression()?));
+                        if !self.eat_symbol(",") {
+                            break;
+                        }
+                    }
+                    self.expect_symbol("}")?;
+                }
+                Ok(Expr::Map(entries))
+            },
+            Token::Ident(name) => {
+                self.position += 1;
+                match &name.to_uppercase()[..] {
+                    "TRUE" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "FALSE" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "NULL" => return Ok(Expr::Literal(Value::Null)),
+                    "CASE" => return Err(unsupported("CASE")),
+                    _ => {},
+                }
+
+                if !self.eat_symbol("(") {
+                    return Ok(Expr::Variable(name));
+                }
+
+                let name = name.to_lowercase();
+                if name == "count" && self.is_symbol("*") && self.is_symbol_at(1, ")") {
+                    self.position += 2;
+                    return Ok(Expr::CountStar);
+                }
+
+                let distinct = self.eat_keyword("DISTINCT");
+                let mut arguments = Vec::new();
+                if !self.eat_symbol(")") {
+                    loop {
+                        arguments.push(self.expression()?);
+                        if !self.eat_symbol(",") {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(")")?;
+                }
+
+                Ok(Expr::Call(name, distinct, arguments))
+            },
+            _ => self.error(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::value::Value;
+
+    fn variable(name: &str) -> Expr {
+        Expr::Variable(name.to_owned())
+    }
+
+    #[test]
+    fn parse_match_where_return() {
+        let query = parse("MATCH (n:Person {name: {name}})-[r:KNOWS]->(:Person) \
+                           WHERE n.age >= 18 AND NOT n:Robot \
+                           RETURN n.name, count(r) AS friends ORDER BY friends DESC LIMIT 10")
+            .unwrap();
+
+        assert_eq!(2, query.clauses.len());
+        match query.clauses[0] {
+            Clause::Match { optional, ref patterns, ref filter } => {
+                assert!(!optional);
+                assert_eq!("n", patterns[0].start.variable);
+                assert_eq!(vec!["Person".to_owned()], patterns[0].start.labels);
+                assert_eq!(Some(Expr::Map(vec![("name".to_owned(),
+                                                Expr::Parameter("name".to_owned()))])),
+                           patterns[0].start.properties);
+
+                let (ref relationship, ref end) = patterns[0].steps[0];
+                assert_eq!(Direction::Outgoing, relationship.direction);
+                assert_eq!(vec!["KNOWS".to_owned()], relationship.types);
+                assert!(end.variable.starts_with(ANONYMOUS));
+                assert!(filter.is_some());
+            },
+            ref clause => panic!("Unexpected clause {:?}", clause),
+        }
+
+        match query.clauses[1] {
+            Clause::Return(ref projection) => {
+                assert_eq!(vec!["n.name".to_owned(), "friends".to_owned()],
+                           projection.items.iter().map(|i| i.1.clone()).collect::<Vec<_>>());
+                assert_eq!(vec![(variable("friends"), false)], projection.order);
+                assert_eq!(Some(Expr::Literal(Value::Int(10))), projection.limit);
+            },
+            ref clause => panic!("Unexpected clause {:?}", clause),
+        }
+    }
+
+    #[test]
+    fn parse_relationship_directions() {
+        let query = parse("MATCH (a)<-[:A]-(b)--(c)-->(d) RETURN a").unwrap();
+        match query.clauses[0] {
+            Clause::Match { ref patterns, .. } => {
+                let directions: Vec<Direction> = patterns[0].steps.iter()
+                    .map(|step| step.0.direction)
+                    .collect();
+                assert_eq!(vec![Direction::Incoming, Direction::Both, Direction::Outgoing],
+                           directions);
+            },
+            ref clause => panic!("Unexpected clause {:?}", clause),
+        }
+    }
+
+    #[test]
+    fn parse_updates() {
+        let query = parse("MERGE (n:A {id: $id}) ON CREATE SET n.created = true \
+                           SET n += {b: 'x'}, n:B REMOVE n.c DETACH DELETE n").unwrap();
+        assert_eq!(4, query.clauses.len());
+
+        match query.clauses[1] {
+            Clause::Set(ref items) => {
+                assert_eq!(SetItem::Labels("n".to_owned(), vec!["B".to_owned()]), items[1]);
+            },
+            ref clause => panic!("Unexpected clause {:?}", clause),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("MATCH (n RETURN n").is_err());
+        assert!(parse("MATCH (n)").is_err());
+        assert!(parse("RETURN 1 RETURN 2").is_err());
+        assert!(parse("RETURN 'unterminated").is_err());
+
+        let error = parse("CREATE INDEX ON :A(b)").unwrap_err();
+        assert!(error.message.contains("not supported"));
+        assert!(parse("MATCH (a)-[*]->(b) RETURN a").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use rustc_serialize::json::Json;
+
+/// A value in the in-memory graph
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+    Node(u64),
+    Relationship(u64),
+}
+
+impl Value {
+    pub fn from_json(json: &Json) -> Value {
+        match *json {
+            Json::Null => Value::Null,
+            Json::Boolean(value) => Value::Bool(value),
+            Json::I64(value) => Value::Int(value),
+            Json::U64(value) => Value::Int(value as i64),
+            Json::F64(value) => Value::Float(value),
+            Json::String(ref value) => Value::String(value.clone()),
+            Json::Array(ref values) => Value::List(values.iter().map(Value::from_json).collect()),
+            Json::Object(ref values) => Value::Map(values.iter()
+                .map(|(key, value)| (key.clone(), Value::from_json(value)))
+                .collect()),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Boolean",
+            Value::Int(_) => "Integer",
+            Value::Float(_) => "Float",
+            Value::String(_) => "String",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Node(_) => "Node",
+            Value::Relationship(_) => "Relationship",
+        }
+    }
+
+    /// Returns `true` if the value can be stored as a property
+    pub fn is_property(&self) -> bool {
+        match *self {
+            Value::List(ref values) => values.iter().all(|value| value.is_primitive()),
+            ref value => value.is_null() || value.is_primitive(),
+        }
+    }
+
+    fn is_primitive(&self) -> bool {
+        matches!(*self, Value::Bool(_) | Value::Int(_) | Value::Float(_) | Value::String(_))
+    }
+}
+
+/// Compares two values for equality, returning `None` if the result is unknown because of nulls
+pub fn equals(a: &Value, b: &Value) -> Option<bool> {
+    match (a, b) {
+        (&Value::Null, _) | (_, &Value::Null) => None,
+        (&Value::Int(a), &Value::Float(b)) => Some(a as f64 == b),
+        (&Value::Float(a), &Value::Int(b)) => Some(a == b as f64),
+        (Value::List(a), Value::List(b)) => {
+            if a.len() != b.len() {
+                return Some(false);
+            }
+
+            all_equal(a.iter().zip(b.iter()))
+        },
+        (Value::Map(a), Value::Map(b)) => {
+            if a.len() != b.len() || a.keys().zip(b.keys()).any(|(a, b)| a != b) {
+                return Some(false);
+            }
+
+            all_equal(a.values().zip(b.values()))
+        },
+        (a, b) => Some(a == b),
+    }
+}
+
+fn all_equal<'a, I: Iterator<Item = (&'a Value, &'a Value)>>(pairs: I) -> Option<bool> {
+    let mut result = Some(true);
+    for (a, b) in pairs {
+        match equals(a, b) {
+            Some(false) => return Some(false),
+            None => result = None,
+            Some(true) => {},
+        }
+    }
+
+    result
+}
+
+/// Compares two values, returning `None` if they are not comparable
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (&Value::Int(a), &Value::Int(b)) => Some(a.cmp(&b)),
+        (&Value::Int(a), &Value::Float(b)) => (a as f64).partial_cmp(&b),
+        (&Value::Float(a), &Value::Int(b)) => a.partial_cmp(&(b as f64)),
+        (&Value::Float(a), &Value::Float(b)) => a.partial_cmp(&b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (&Value::Bool(a), &Value::Bool(b)) => Some(a.cmp(&b)),
+        _ => None,
+    }
+}
+
+/// Orders any two values, as `ORDER BY` does, with nulls last
+pub fn order(a: &Value, b: &Value) -> Ordering {
+    match compare(a, b) {
+        Some(ordering) => ordering,
+        None => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn rank(value: &Value) -> u8 {
+    match *value {
+        Value::Map(_) => 0,
+        Value::Node(_) => 1,
+        Value::Relationship(_) => 2,
+        Value::List(_) => 3,
+        Value::String(_) => 4,
+        Value::Bool(_) => 5,
+        Value::Int(_) | Value::Float(_) => 6,
+        Value::Null => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use super::*;
+
+    #[test]
+    fn equality_with_nulls_and_numbers() {
+        assert_eq!(Some(true), equals(&Value::Int(1), &Value::Float(1.0)));
+        assert_eq!(Some(false), equals(&Value::Int(1), &Value::String("1".to_owned())));
+        assert_eq!(None, equals(&Value::Null, &Value::Null));
+        assert_eq!(None, equals(&Value::List(vec![Value::Int(1), Value::Null]),
+                                &Value::List(vec![Value::Int(1), Value::Int(2)])));
+        assert_eq!(Some(false), equals(&Value::List(vec![Value::Int(3), Value::Null]),
+                                       &Value::List(vec![Value::Int(1), Value::Int(2)])));
+    }
+
+    #[test]
+    fn order_puts_nulls_last() {
+        let mut values = vec![Value::Null, Value::Int(2), Value::Float(1.5), Value::Int(1)];
+        values.sort_by(order);
+        assert_eq!(vec![Value::Int(1), Value::Float(1.5), Value::Int(2), Value::Null], values);
+        assert_eq!(None, compare(&Value::Int(1), &Value::String("a".to_owned())));
+        assert_eq!(Some(Ordering::Less),
+                   compare(&Value::String("a".to_owned()), &Value::String("b".to_owned())));
+    }
+}
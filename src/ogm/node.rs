@@ -0,0 +1,129 @@
+//! Mapping of rust structs to nodes
+
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{Json, ToJson};
+
+use ::cypher::{escape_identifier, Cypher, Statement};
+use ::error::GraphError;
+use super::{key_pattern, with_params, Properties};
+
+/// Maps a rust struct to nodes with a given label
+///
+/// Nodes are identified by their key properties, which are used to find, merge, update and delete
+/// them. Implementations are usually generated with `#[derive(GraphNode)]`, marking the key
+/// fields with `#[key]` and optionally setting the label with `#[label = "..."]`.
+pub trait GraphNode: Decodable + Sized {
+    /// Returns the label of the nodes
+    fn label() -> &'static str;
+
+    /// Returns the names of the key properties
+    fn key_properties() -> Vec<&'static str>;
+
+    /// Returns the key properties of the node
+    fn key(&self) -> Properties;
+
+    /// Returns all properties of the node, including the key properties
+    fn properties(&self) -> Properties;
+
+    /// Returns the statement that creates the node
+    fn create_statement(&self) -> Statement {
+        let text = format!("CREATE (n:{} {{props}}) RETURN n", escape_identifier(Self::label()));
+        Statement::new(&text).with_param("props", self.properties())
+    }
+
+    /// Returns the statement that creates the node or updates the node with the same key
+    fn merge_statement(&self) -> Statement {
+        let (pattern, params) = key_pattern("n", Self::label(), self.key(), "key");
+        let text = format!("MERGE {} SET n = {{props}} RETURN n", pattern);
+        with_params(&text, params).with_param("props", self.properties())
+    }
+
+    /// Returns the statement that updates the properties of the node with the same key
+    fn update_statement(&self) -> Statement {
+        let (pattern, params) = key_pattern("n", Self::label(), self.key(), "key");
+        let text = format!("MATCH {} SET n = {{props}} RETURN n", pattern);
+        with_params(&text, params).with_param("props", self.properties())
+    }
+
+    /// Returns the statement that deletes the node with the same key and its relationships
+    fn delete_statement(&self) -> Statement {
+        let (pattern, params) = key_pattern("n", Self::label(), self.key(), "key");
+        let text = format!("MATCH {} OPTIONAL MATCH (n)-[r]-() DELETE r, n", pattern);
+        with_params(&text, params)
+    }
+
+    /// Returns the statement that finds the node with the given key
+    ///
+    /// For nodes with more than one key property, `key` must be a tuple with the values in the
+    /// same order as the key fields are declared.
+    fn find_by_key_statement<K: ToJson>(key: K) -> Statement {
+        let names = Self::key_properties();
+        let values = match key.to_json() {
+            Json::Array(ref values) if names.len() > 1 => values.clone(),
+            value => vec![value],
+        };
+
+        let key = names.into_iter().map(|name| name.to_owned()).zip(values).collect();
+        let (pattern, params) = key_pattern("n", Self::label(), key, "key");
+        with_params(&format!("MATCH {} RETURN n", pattern), params)
+    }
+
+    /// Creates or updates the node, returning it as stored in the database
+    fn save(&self, cypher: &Cypher) -> Result<Self, GraphError> {
+        let mut results: Vec<(Self,)> = cypher.exec(self.merge_statement())?;
+        results.pop()
+            .map(|row| row.0)
+            .ok_or(GraphError::new("No node returned when saving"))
+    }
+
+    /// Deletes the node and its relationships
+    fn delete(&self, cypher: &Cypher) -> Result<(), GraphError> {
+        cypher.exec::<()>(self.delete_statement())?;
+        Ok(())
+    }
+
+    /// Finds the node with the given key
+    fn find_by_key<K: ToJson>(cypher: &Cypher, key: K) -> Result<Option<Self>, GraphError> {
+        let mut results: Vec<(Self,)> = cypher.exec(Self::find_by_key_statement(key))?;
+        Ok(results.pop().map(|row| row.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::ogm::test_nodes::Language;
+
+    fn rust() -> Language {
+        Language { name: "Rust".to_owned(), safe: true }
+    }
+
+    #[test]
+    fn create_statement() {
+        let statement = rust().create_statement();
+        assert_eq!(statement.statement(), "CREATE (n:`Language` {props}) RETURN n");
+        assert_eq!(statement.parameters().len(), 1);
+    }
+
+    #[test]
+    fn merge_statement() {
+        let statement = rust().merge_statement();
+        assert_eq!(statement.statement(),
+                   "MERGE (n:`Language` {`name`: {key_0}}) SET n = {props} RETURN n");
+        assert_eq!(statement.param("key_0").unwrap().as_string(), Some("Rust"));
+    }
+
+    #[test]
+    fn delete_statement() {
+        let statement = rust().delete_statement();
+        assert_eq!(statement.statement(),
+                   "MATCH (n:`Language` {`name`: {key_0}}) OPTIONAL MATCH (n)-[r]-() DELETE r, n");
+    }
+
+    #[test]
+    fn find_by_key_statement() {
+        let statement = Language::find_by_key_statement("Rust".to_owned());
+        assert_eq!(statement.statement(), "MATCH (n:`Language` {`name`: {key_0}}) RETURN n");
+        assert_eq!(statement.param("key_0").unwrap().as_string(), Some("Rust"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Mapping of rust structs to relationships
+
+use rustc_serialize::Decodable;
+
+use ::cypher::{escape_identifier, Cypher, Statement};
+use ::error::GraphError;
+use super::{key_pattern, with_params, GraphNode, Properties};
+
+/// Maps a rust struct to relationships of a given type between two kinds of nodes
+///
+/// The start and end nodes are identified by their key properties. Implementations are usually
+/// generated with `#[derive(GraphRelationship)]`, setting the node types with `#[start = "..."]`
+/// and `#[end = "..."]` and optionally the relationship type with `#[rel_type = "..."]`.
+///
+/// Results are returned as `(start, relationship, end)` triples.
+pub trait GraphRelationship: Decodable + Sized {
+    /// Type of the start node
+    type Start: GraphNode;
+
+    /// Type of the end node
+    type End: GraphNode;
+
+    /// Returns the type of the relationships
+    fn rel_type() -> &'static str;
+
+    /// Returns the properties of the relationship
+    fn properties(&self) -> Properties;
+
+    /// Returns the statement that creates the relationship between the nodes, or updates the
+    /// properties of the existing one
+    fn merge_statement(&self, start: &Self::Start, end: &Self::End) -> Statement {
+        let (text, params) = endpoints_pattern::<Self>(start, end);
+        let text = format!("{} MERGE (a)-[r:{}]->(b) SET r = {{props}} RETURN a, r, b",
+                           text,
+                           escape_identifier(Self::rel_type()));
+
+        with_params(&text, params).with_param("props", self.properties())
+    }
+
+    /// Returns the statement that deletes the relationships between the nodes
+    fn delete_statement(start: &Self::Start, end: &Self::End) -> Statement {
+        let (text, params) = endpoints_pattern::<Self>(start, end);
+        let text = format!("{} MATCH (a)-[r:{}]->(b) DELETE r",
+                           text,
+                           escape_identifier(Self::rel_type()));
+
+        with_params(&text, params)
+    }
+
+    /// Returns the statement that finds all relationships of this type
+    fn find_all_statement() -> Statement {
+        Statement::new(&format!("MATCH (a:{})-[r:{}]->(b:{}) RETURN a, r, b",
+                                escape_identifier(Self::Start::label()),
+                                escape_identifier(Self::rel_type()),
+                                escape_identifier(Self::End::label())))
+    }
+
+    /// Returns the statement that finds the relationships starting at the given node
+    fn find_from_statement(start: &Self::Start) -> Statement {
+        let (pattern, params) = key_pattern("a", Self::Start::label(), start.key(), "start");
+        let text = format!("MATCH {}-[r:{}]->(b:{}) RETURN a, r, b",
+                           pattern,
+                           escape_identifier(Self::rel_type()),
+                           escape_identifier(Self::End::label()));
+
+        with_params(&text, params)
+    }
+
+    /// Creates or updates the relationship between the nodes
+    ///
+    /// Both nodes must already exist.
+    fn save(&self, cypher: &Cypher, start: &Self::Start, end: &Self::End)
+        -> Result<(Self::Start, Self, Self::End), GraphError>
+    {
+        let mut results = cypher.exec(self.merge_statement(start, end))?;
+        results.pop().ok_or(GraphError::new("No relationship returned when saving"))
+    }
+
+    /// Deletes the relationships between the nodes
+    fn delete(cypher: &Cypher, start: &Self::Start, end: &Self::End) -> Result<(), GraphError> {
+        cypher.exec::<()>(Self::delete_statement(start, end))?;
+        Ok(())
+    }
+
+    /// Finds all relationships of this type
+    #[allow(clippy::type_complexity)]
+    fn find_all(cypher: &Cypher) -> Result<Vec<(Self::Start, Self, Self::End)>, GraphError> {
+        cypher.exec(Self::find_all_statement())
+    }
+
+    /// Finds the relationships starting at the given node
+    #[allow(clippy::type_complexity)]
+    fn find_from(cypher: &Cypher, start: &Self::Start)
+        -> Result<Vec<(Self::Start, Self, Self::End)>, GraphError>
+    {
+        cypher.exec(Self::find_from_statement(start))
+    }
+}
+
+/// Builds the statement text that matches the start node as `a` and the end node as `b`
+fn endpoints_pattern<R: GraphRelationship>(start: &R::Start, end: &R::End) -> (String, Properties) {
+    let (start_pattern, mut params) = key_pattern("a", R::Start::label(), start.key(), "start");
+    let (end_pattern, end_params) = key_pattern("b", R::End::label(), end.key(), "end");
+    params.extend(end_params);
+
+    (format!("MATCH {} MATCH {}", start_pattern, end_pattern), params)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use rustc_serialize::json::ToJson;
+    use super::*;
+    use ::ogm::Properties;
+    use ::ogm::test_nodes::Person;
+
+    struct Knows {
+        since: i32,
+    }
+
+    impl_decodable!(Knows { since });
+
+    impl GraphRelationship for Knows {
+        type Start = Person;
+        type End = Person;
+
+        fn rel_type() -> &'static str {
+            "KNOWS"
+        }
+
+        fn properties(&self) -> Properties {
+            let mut properties = BTreeMap::new();
+            properties.insert("since".to_owned(), self.since.to_json());
+            properties
+        }
+    }
+
+    fn people() -> (Person, Person) {
+        (Person { name: "Alice".to_owned() }, Person { name: "Bob".to_owned() })
+    }
+
+    #[test]
+    fn merge_statement() {
+        let (alice, bob) = people();
+        let statement = Knows { since: 2015 }.merge_statement(&alice, &bob);
+
+        assert_eq!(statement.statement(),
+                   "MATCH (a:`Person` {`name`: {start_0}}) MATCH (b:`Person` {`name`: {end_0}}) \
+                    MERGE (a)-[r:`KNOWS`]->(b) SET r = {props} RETURN a, r, b");
+        assert_eq!(statement.param("start_0").unwrap().as_string(), Some("Alice"));
+        assert_eq!(statement.param("end_0").unwrap().as_string(), Some("Bob"));
+    }
+
+    #[test]
+    fn find_statements() {
+        let (alice, _) = people();
+
+        assert_eq!(Knows::find_all_statement().statement(),
+                   "MATCH (a:`Person`)-[r:`KNOWS`]->(b:`Person`) RETURN a, r, b");
+        assert_eq!(Knows::find_from_statement(&alice).statement(),
+                   "MATCH (a:`Person` {`name`: {start_0}})-[r:`KNOWS`]->(b:`Person`) RETURN a, r, b");
+    }
+}
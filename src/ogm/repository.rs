@@ -0,0 +1,245 @@
+//! Routine access to mapped nodes without writing cypher
+//!
+//! A `Repository` runs on any `Executor`, so the same code works with statements that commit
+//! immediately and inside a transaction.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use rusted_cypher::ogm::Repository;
+//!
+//! let mut languages = Repository::<Language, _>::new(graph.cypher());
+//! let id = languages.save(&rust).unwrap();
+//! assert_eq!(languages.find_by_id(id).unwrap(), Some(rust));
+//!
+//! // Inside a transaction
+//! let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+//! Repository::<Language, _>::new(&mut transaction).save_all(&[python, ruby]).unwrap();
+//! transaction.commit::<()>(None).unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use rustc_serialize::json::{Json, ToJson};
+
+use ::cypher::{escape_identifier, Executor, Statement};
+use ::error::GraphError;
+use super::{key_pattern, with_params, GraphNode};
+
+/// Provides typed access to the nodes mapped by `T`
+pub struct Repository<T: GraphNode, E: Executor> {
+    executor: E,
+    _entity: PhantomData<T>,
+}
+
+impl<T: GraphNode, E: Executor> Repository<T, E> {
+    /// Creates a new `Repository` running its statements on `executor`
+    pub fn new(executor: E) -> Self {
+        Repository {
+            executor: executor,
+            _entity: PhantomData,
+        }
+    }
+
+    /// Consumes the `Repository`, returning the underlying `Executor`
+    pub fn into_inner(self) -> E {
+        self.executor
+    }
+
+    fn label() -> String {
+        escape_identifier(T::label())
+    }
+
+    fn find_one(&mut self, statement: Statement) -> Result<Option<T>, GraphError> {
+        let mut results: Vec<(T,)> = self.executor.exec(statement)?;
+        Ok(results.pop().map(|row| row.0))
+    }
+
+    fn find_many(&mut self, statement: Statement) -> Result<Vec<T>, GraphError> {
+        let results: Vec<(T,)> = self.executor.exec(statement)?;
+        Ok(results.into_iter().map(|row| row.0).collect())
+    }
+
+    /// Creates or updates the node with the same key, returning its id
+    pub fn save(&mut self, entity: &T) -> Result<u64, GraphError> {
+        let (pattern, params) = key_pattern("n", T::label(), entity.key(), "key");
+        let text = format!("MERGE {} SET n = {{props}} RETURN id(n)", pattern);
+        let statement = with_params(&text, params).with_param("props", entity.properties());
+
+        let mut results: Vec<(u64,)> = self.executor.exec(statement)?;
+        results.pop()
+            .map(|row| row.0)
+            .ok_or(GraphError::new("No node returned when saving"))
+    }
+
+    /// Creates or updates all nodes in a single statement, returning their ids
+    pub fn save_all(&mut self, entities: &[T]) -> Result<Vec<u64>, GraphError> {
+        if entities.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<Json> = entities.iter().map(|entity| {
+            let mut row = BTreeMap::new();
+            row.insert("key".to_owned(), Json::Object(entity.key()));
+            row.insert("props".to_owned(), Json::Object(entity.properties()));
+            Json::Object(row)
+        }).collect();
+
+        let key: Vec<String> = T::key_properties().into_iter().map(|name| {
+            let name = escape_identifier(name);
+            format!("{}: row.key.{}", name, name)
+        }).collect();
+
+        let text = format!("UNWIND {{rows}} AS row MERGE (n:{} {{{}}}) SET n = row.props RETURN id(n)",
+                           Self::label(),
+                           key.join(", "));
+        let statement = Statement::new(&text).with_param("rows", rows);
+
+        let results: Vec<(u64,)> = self.executor.exec(statement)?;
+        Ok(results.into_iter().map(|row| row.0).collect())
+    }
+
+    /// Finds the node with the given id
+    pub fn find_by_id(&mut self, id: u64) -> Result<Option<T>, GraphError> {
+        let text = format!("MATCH (n:{}) WHERE id(n) = {{id}} RETURN n", Self::label());
+        self.find_one(Statement::new(&text).with_param("id", id))
+    }
+
+    /// Finds the node with the given key
+    ///
+    /// For nodes with more than one key property, `key` must be a tuple with the values in the
+    /// same order as the key fields are declared.
+    pub fn find_by_key<K: ToJson>(&mut self, key: K) -> Result<Option<T>, GraphError> {
+        self.find_one(T::find_by_key_statement(key))
+    }
+
+    /// Finds the nodes where `property` is equal to `value`
+    pub fn find_by<V: ToJson>(&mut self, property: &str, value: V) -> Result<Vec<T>, GraphError> {
+        let text = format!("MATCH (n:{}) WHERE n.{} = {{value}} RETURN n",
+                           Self::label(),
+                           escape_identifier(property));
+        self.find_many(Statement::new(&text).with_param("value", value))
+    }
+
+    /// Finds all nodes
+    pub fn find_all(&mut self) -> Result<Vec<T>, GraphError> {
+        let text = format!("MATCH (n:{}) RETURN n", Self::label());
+        self.find_many(Statement::new(&text))
+    }
+
+    /// Finds a page of nodes, ordered by their key properties
+    ///
+    /// Pages are numbered from zero. Fails without querying when the page starts past `u64::MAX`.
+    pub fn find_page(&mut self, page: u64, size: u64) -> Result<Vec<T>, GraphError> {
+        let skip = page.checked_mul(size).ok_or_else(|| GraphError::new(&format!(
+            "Page {} of size {} is out of range", page, size)))?;
+
+        let order: Vec<String> = T::key_properties().into_iter()
+            .map(|name| format!("n.{}", escape_identifier(name)))
+            .collect();
+
+        let text = format!("MATCH (n:{}) RETURN n ORDER BY {} SKIP {{skip}} LIMIT {{limit}}",
+                           Self::label(),
+                           order.join(", "));
+        let statement = Statement::new(&text)
+            .with_param("skip", skip)
+            .with_param("limit", size);
+
+        self.find_many(statement)
+    }
+
+    /// Counts the nodes
+    pub fn count(&mut self) -> Result<u64, GraphError> {
+        let text = format!("MATCH (n:{}) RETURN count(n)", Self::label());
+        let results: Vec<(u64,)> = self.executor.exec(Statement::new(&text))?;
+        Ok(results.first().map(|row| row.0).unwrap_or(0))
+    }
+
+    /// Deletes the node with the same key and its relationships
+    pub fn delete(&mut self, entity: &T) -> Result<(), GraphError> {
+        self.executor.exec::<()>(entity.delete_statement())?;
+        Ok(())
+    }
+
+    /// Deletes the node with the given id and its relationships
+    pub fn delete_by_id(&mut self, id: u64) -> Result<(), GraphError> {
+        let text = format!("MATCH (n:{}) WHERE id(n) = {{id}} OPTIONAL MATCH (n)-[r]-() DELETE r, n",
+                           Self::label());
+        self.executor.exec::<()>(Statement::new(&text).with_param("id", id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::Decodable;
+    use super::*;
+    use ::cypher::{Executor, Statement};
+    use ::error::GraphError;
+    use ::ogm::test_nodes::Language;
+
+    /// Records the statements instead of sending them
+    #[derive(Default)]
+    struct Recorder {
+        statements: Vec<Statement>,
+    }
+
+    impl Executor for Recorder {
+        fn exec<T: Decodable>(&mut self, statement: Statement) -> Result<Vec<T>, GraphError> {
+            self.statements.push(statement);
+            Ok(Vec::new())
+        }
+    }
+
+    fn last_statement(repository: Repository<Language, Recorder>) -> Statement {
+        repository.into_inner().statements.pop().unwrap()
+    }
+
+    #[test]
+    fn save_all_statement() {
+        let mut repository = Repository::new(Recorder::default());
+        let languages = vec![Language { name: "Rust".to_owned(), safe: true },
+                             Language { name: "C".to_owned(), safe: false }];
+        repository.save_all(&languages).unwrap();
+
+        let statement = last_statement(repository);
+        assert_eq!(statement.statement(),
+                   "UNWIND {rows} AS row MERGE (n:`Language` {`name`: row.key.`name`}) \
+                    SET n = row.props RETURN id(n)");
+        assert_eq!(statement.param("rows").unwrap().as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn find_by_statement() {
+        let mut repository = Repository::new(Recorder::default());
+        repository.find_by("name", "Rust".to_owned()).unwrap();
+
+        let statement = last_statement(repository);
+        assert_eq!(statement.statement(), "MATCH (n:`Language`) WHERE n.`name` = {value} RETURN n");
+    }
+
+    #[test]
+    fn find_page_statement() {
+        let mut repository = Repository::new(Recorder::default());
+        repository.find_page(2, 10).unwrap();
+
+        let statement = last_statement(repository);
+        assert_eq!(statement.statement(),
+                   "MATCH (n:`Language`) RETURN n ORDER BY n.`name` SKIP {skip} LIMIT {limit}");
+        assert_eq!(statement.param("skip").unwrap().as_u64(), Some(20));
+    }
+
+    #[test]
+    fn find_page_out_of_range() {
+        let mut repository: Repository<Language, _> = Repository::new(Recorder::default());
+        let error = repository.find_page(u64::MAX / 2, 3).err().unwrap();
+        assert!(error.to_string().contains("out of range"));
+        assert!(repository.into_inner().statements.is_empty());
+    }
+
+    #[test]
+    fn count_without_results() {
+        let mut repository: Repository<Language, _> = Repository::new(Recorder::default());
+        assert_eq!(repository.count().unwrap(), 0);
+    }
+}
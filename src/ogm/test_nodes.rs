@@ -0,0 +1,58 @@
+//! Nodes shared by the tests of the `ogm` module
+
+use ::ogm::{GraphNode, Properties, ToJson};
+
+pub struct Language {
+    pub name: String,
+    pub safe: bool,
+}
+
+impl_decodable!(Language { name, safe });
+
+impl GraphNode for Language {
+    fn label() -> &'static str {
+        "Language"
+    }
+
+    fn key_properties() -> Vec<&'static str> {
+        vec!["name"]
+    }
+
+    fn key(&self) -> Properties {
+        let mut key = Properties::new();
+        key.insert("name".to_owned(), self.name.to_json());
+        key
+    }
+
+    fn properties(&self) -> Properties {
+        let mut properties = self.key();
+        properties.insert("safe".to_owned(), self.safe.to_json());
+        properties
+    }
+}
+
+pub struct Person {
+    pub name: String,
+}
+
+impl_decodable!(Person { name });
+
+impl GraphNode for Person {
+    fn label() -> &'static str {
+        "Person"
+    }
+
+    fn key_properties() -> Vec<&'static str> {
+        vec!["name"]
+    }
+
+    fn key(&self) -> Properties {
+        let mut key = Properties::new();
+        key.insert("name".to_owned(), self.name.to_json());
+        key
+    }
+
+    fn properties(&self) -> Properties {
+        self.key()
+    }
+}
@@ -0,0 +1,689 @@
+//! In-process mock of the neo4j REST API, for tests that should not need a running server
+//!
+//! Only available with the `testing` feature.
+//!
+//! `MockServer` listens on a random local port and emulates the service root and the transaction
+//! endpoints: beginning a transaction, running statements in it, committing, rolling back and
+//! running statements in a single request through `/transaction/commit`.
+//!
+//! Each statement received gets the next response queued with `push_response`, or an empty
+//! result when there is none. Responses registered with `respond_to` are used instead for every
+//! statement containing the given text. As in neo4j, a statement that fails stops the request
+//! and rolls back the transaction it was sent in.
+//!
+//! Requests and statements received are recorded, so tests can assert on what was sent.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate rustc_serialize;
+//! # extern crate rusted_cypher;
+//! use rustc_serialize::json::Json;
+//! use rusted_cypher::GraphClient;
+//! use rusted_cypher::testing::{MockResponse, MockServer};
+//!
+//! # fn main() {
+//! let server = MockServer::start().unwrap();
+//! server.push_response(MockResponse::rows(&["name"], vec![vec![Json::String("Rust".to_owned())]]));
+//! server.respond_to("BROKEN", MockResponse::error("Neo.ClientError.Statement.SyntaxError", "Invalid input"));
+//!
+//! let graph = GraphClient::connect(&server.url()).unwrap();
+//!
+//! let names: Vec<(String,)> = graph.cypher().exec("MATCH (n:LANG) RETURN n.name".into()).unwrap();
+//! assert_eq!(vec![("Rust".to_owned(),)], names);
+//! assert!(graph.cypher().exec::<()>("BROKEN".into()).is_err());
+//!
+//! server.assert_received("MATCH (n:LANG)");
+//! server.assert_statement_count(2);
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use hyper::header::{Connection, ContentType, Location};
+use hyper::net::Fresh;
+use hyper::server::{Handler, Listening, Request, Response, Server};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use rustc_serialize::json::{Json, ToJson};
+use time;
+
+use ::error::GraphError;
+use ::graph::GraphClient;
+
+const BASE_PATH: &'static str = "/db/data";
+const DEFAULT_VERSION: &'static str = "3.0.0";
+
+/// Response to a single statement
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockResponse {
+    kind: ResponseKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ResponseKind {
+    Result {
+        columns: Vec<String>,
+        rows: Vec<Vec<Json>>,
+        stats: BTreeMap<String, Json>,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
+    Http(u16),
+}
+
+impl MockResponse {
+    /// A result without columns or rows
+    pub fn empty() -> Self {
+        MockResponse::rows(&[], Vec::new())
+    }
+
+    /// A result with the given columns and rows
+    pub fn rows(columns: &[&str], rows: Vec<Vec<Json>>) -> Self {
+        MockResponse {
+            kind: ResponseKind::Result {
+                columns: columns.iter().map(|column| (*column).to_owned()).collect(),
+                rows: rows,
+                stats: BTreeMap::new(),
+            }
+        }
+    }
+
+    /// Sets a counter of the update statistics, like `nodes_created`
+    ///
+    /// Statistics are only sent if the statement requested them with `Statement::with_stats`.
+    pub fn with_stat(mut self, name: &str, value: u64) -> Self {
+        if let ResponseKind::Result { ref mut stats, .. } = self.kind {
+            stats.insert(name.to_owned(), value.to_json());
+        }
+        self
+    }
+
+    /// A neo4j error, like `Neo.ClientError.Statement.SyntaxError`
+    pub fn error(code: &str, message: &str) -> Self {
+        MockResponse {
+            kind: ResponseKind::Error {
+                code: code.to_owned(),
+                message: message.to_owned(),
+            }
+        }
+    }
+
+    /// Fails the whole request with the given HTTP status and an empty body
+    pub fn http_error(status: u16) -> Self {
+        MockResponse {
+            kind: ResponseKind::Http(status),
+        }
+    }
+}
+
+/// A request received by the `MockServer`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Option<Json>,
+    pub authorization: Option<String>,
+    /// The `max-execution-time` header, sent when a transaction with a timeout begins
+    pub max_execution_time: Option<String>,
+}
+
+/// A statement received by the `MockServer`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedStatement {
+    pub statement: String,
+    pub parameters: BTreeMap<String, Json>,
+    /// Id of the transaction the statement was sent in, `None` for `/transaction/commit`
+    pub transaction: Option<u64>,
+}
+
+struct State {
+    base_url: String,
+    version: String,
+    responses: VecDeque<MockResponse>,
+    rules: Vec<(String, MockResponse)>,
+    requests: Vec<ReceivedRequest>,
+    statements: Vec<ReceivedStatement>,
+    next_transaction: u64,
+    open: Vec<u64>,
+    committed: Vec<u64>,
+    rolled_back: Vec<u64>,
+}
+
+/// Reply to a request: status, location header and body
+struct Reply(u16, Option<String>, String);
+
+/// Local HTTP server emulating the neo4j REST API
+///
+/// The server stops when dropped.
+pub struct MockServer {
+    listening: Listening,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port
+    pub fn start() -> Result<Self, GraphError> {
+        let state = Arc::new(Mutex::new(State {
+            base_url: String::new(),
+            version: DEFAULT_VERSION.to_owned(),
+            responses: VecDeque::new(),
+            rules: Vec::new(),
+            requests: Vec::new(),
+            statements: Vec::new(),
+            next_transaction: 1,
+            open: Vec::new(),
+            committed: Vec::new(),
+            rolled_back: Vec::new(),
+        }));
+
+        let server = Server::http("127.0.0.1:0")?;
+        let listening = server.handle(MockHandler { state: state.clone() })?;
+
+        state.lock().unwrap().base_url = format!("http://{}{}", listening.socket, BASE_PATH);
+
+        Ok(MockServer {
+            listening: listening,
+            state: state,
+        })
+    }
+
+    /// Sets the version reported in the service root, `3.0.0` by default
+    pub fn with_version(self, version: &str) -> Self {
+        self.set_version(version);
+        self
+    }
+
+    pub fn set_version(&self, version: &str) {
+        self.state.lock().unwrap().version = version.to_owned();
+    }
+
+    /// Returns the url of the service root, with credentials, to use in `GraphClient::connect`
+    pub fn url(&self) -> String {
+        format!("http://neo4j:neo4j@{}{}", self.listening.socket, BASE_PATH)
+    }
+
+    /// Connects a `GraphClient` to this server
+    pub fn connect(&self) -> Result<GraphClient, GraphError> {
+        GraphClient::connect(&self.url())
+    }
+
+    /// Queues the response to the next statement
+    pub fn with_response(self, response: MockResponse) -> Self {
+        self.push_response(response);
+        self
+    }
+
+    pub fn push_response(&self, response: MockResponse) {
+        self.state.lock().unwrap().responses.push_back(response);
+    }
+
+    /// Responds to every statement containing `text`, ahead of the queued responses
+    pub fn with_response_to(self, text: &str, response: MockResponse) -> Self {
+        self.respond_to(text, response);
+        self
+    }
+
+    pub fn respond_to(&self, text: &str, response: MockResponse) {
+        self.state.lock().unwrap().rules.push((text.to_owned(), response));
+    }
+
+    /// Returns all requests received, in order
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Returns all statements received, in order
+    pub fn statements(&self) -> Vec<ReceivedStatement> {
+        self.state.lock().unwrap().statements.clone()
+    }
+
+    /// Returns the ids of the transactions begun and not yet committed or rolled back
+    pub fn open_transactions(&self) -> Vec<u64> {
+        self.state.lock().unwrap().open.clone()
+    }
+
+    pub fn committed_transactions(&self) -> Vec<u64> {
+        self.state.lock().unwrap().committed.clone()
+    }
+
+    /// Returns the ids of the transactions rolled back, by request or because of an error
+    pub fn rolled_back_transactions(&self) -> Vec<u64> {
+        self.state.lock().unwrap().rolled_back.clone()
+    }
+
+    /// Forgets the requests and statements received so far
+    pub fn clear_received(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.requests.clear();
+        state.statements.clear();
+    }
+
+    /// Panics if no statement containing `text` was received
+    pub fn assert_received(&self, text: &str) {
+        let statements = self.statements();
+        if !statements.iter().any(|s| s.statement.contains(text)) {
+            panic!("No statement containing {:?} was received, got: {:?}",
+                   text, statement_texts(&statements));
+        }
+    }
+
+    /// Panics if any statement containing `text` was received
+    pub fn assert_not_received(&self, text: &str) {
+        let statements = self.statements();
+        if statements.iter().any(|s| s.statement.contains(text)) {
+            panic!("A statement containing {:?} was received: {:?}",
+                   text, statement_texts(&statements));
+        }
+    }
+
+    /// Panics if no statement containing `text` was received with the given parameter
+    pub fn assert_received_with_param<V: ToJson>(&self, text: &str, name: &str, value: V) {
+        let value = value.to_json();
+        let statements = self.statements();
+        let found = statements.iter()
+            .filter(|s| s.statement.contains(text))
+            // Compared as text, since numbers may be parsed back as a different variant
+            .any(|s| s.parameters.get(name).map(|param| param.to_string()) == Some(value.to_string()));
+
+        if !found {
+            panic!("No statement containing {:?} was received with {} = {}, got: {:?}",
+                   text, name, value, statements);
+        }
+    }
+
+    /// Panics unless exactly `count` statements were received
+    pub fn assert_statement_count(&self, count: usize) {
+        let statements = self.statements();
+        if statements.len() != count {
+            panic!("Expected {} statements, received {}: {:?}",
+                   count, statements.len(), statement_texts(&statements));
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = self.listening.close();
+    }
+}
+
+fn statement_texts(statements: &[ReceivedStatement]) -> Vec<&str> {
+    statements.iter().map(|s| &s.statement[..]).collect()
+}
+
+struct MockHandler {
+    state: Arc<Mutex<State>>,
+}
+
+impl Handler for MockHandler {
+    fn handle(&self, mut req: Request, mut res: Response<Fresh>) {
+        let path = match req.uri {
+            RequestUri::AbsolutePath(ref path) => path.clone(),
+            RequestUri::AbsoluteUri(ref url) => url.serialize_path().unwrap_or(String::new()),
+            _ => String::new(),
+        };
+
+        let header = |name: &str| {
+            req.headers.get_raw(name)
+                .and_then(|values| values.first())
+                .map(|value| String::from_utf8_lossy(value).into_owned())
+        };
+        let authorization = header("Authorization");
+        let max_execution_time = header("max-execution-time");
+
+        let mut body = String::new();
+        let _ = req.read_to_string(&mut body);
+
+        let request = ReceivedRequest {
+            method: req.method.to_string(),
+            path: path,
+            body: Json::from_str(&body).ok(),
+            authorization: authorization,
+            max_execution_time: max_execution_time,
+        };
+
+        let Reply(status, location, body) = self.state.lock().unwrap().reply(request);
+
+        // Closing the connection keeps pooled client connections from holding server threads
+        res.headers_mut().set(Connection::close());
+        res.headers_mut().set(ContentType::json());
+        if let Some(location) = location {
+            res.headers_mut().set(Location(location));
+        }
+        *res.status_mut() = StatusCode::from_u16(status);
+
+        let _ = res.send(body.as_bytes());
+    }
+}
+
+impl State {
+    fn reply(&mut self, request: ReceivedRequest) -> Reply {
+        self.requests.push(request.clone());
+
+        let path = request.path.split('?').next().unwrap_or("").trim_end_matches('/');
+        if !path.starts_with(BASE_PATH) {
+            return not_found(&request.path);
+        }
+
+        let segments: Vec<&str> = path[BASE_PATH.len()..].split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        let method = &request.method[..];
+        let body = request.body.as_ref();
+
+        match segments.len() {
+            0 if method == "GET" => self.service_root(),
+            1 if method == "POST" && segments[0] == "transaction" => self.begin(body),
+            2 if method == "POST" && segments[0] == "transaction" && segments[1] == "commit" => {
+                self.autocommit(body)
+            },
+            2 | 3 if segments[0] == "transaction" => {
+                let id = match segments[1].parse() {
+                    Ok(id) if self.open.contains(&id) => id,
+                    _ => return transaction_not_found(segments[1]),
+                };
+
+                match (method, segments.get(2)) {
+                    ("POST", None) => self.exec(id, body),
+                    ("POST", Some(&"commit")) => self.commit(id, body),
+                    ("DELETE", None) => self.rollback(id),
+                    _ => not_found(&request.path),
+                }
+            },
+            _ => not_found(&request.path),
+        }
+    }
+
+    fn service_root(&self) -> Reply {
+        let mut root = BTreeMap::new();
+        for &(key, path) in &[("node", "/node"),
+                              ("node_index", "/index/node"),
+                              ("relationship_index", "/index/relationship"),
+                              ("extensions_info", "/ext"),
+                              ("relationship_types", "/relationship/types"),
+                              ("batch", "/batch"),
+                              ("cypher", "/cypher"),
+                              ("indexes", "/schema/index"),
+                              ("constraints", "/schema/constraint"),
+                              ("transaction", "/transaction"),
+                              ("node_labels", "/labels")] {
+            root.insert(key.to_owned(), format!("{}{}", self.base_url, path).to_json());
+        }
+        root.insert("neo4j_version".to_owned(), self.version.to_json());
+
+        Reply(200, None, Json::Object(root).to_string())
+    }
+
+    fn begin(&mut self, body: Option<&Json>) -> Reply {
+        let id = self.next_transaction;
+        self.next_transaction += 1;
+        self.open.push(id);
+
+        let (results, errors) = match self.run(body, Some(id)) {
+            Ok(results) => results,
+            Err(status) => return Reply(status, None, String::new()),
+        };
+
+        let location = format!("{}/transaction/{}", self.base_url, id);
+        Reply(201, Some(location), self.transaction_body(id, results, errors))
+    }
+
+    fn exec(&mut self, id: u64, body: Option<&Json>) -> Reply {
+        match self.run(body, Some(id)) {
+            Ok((results, errors)) => Reply(200, None, self.transaction_body(id, results, errors)),
+            Err(status) => Reply(status, None, String::new()),
+        }
+    }
+
+    fn commit(&mut self, id: u64, body: Option<&Json>) -> Reply {
+        let (results, errors) = match self.run(body, Some(id)) {
+            Ok(results) => results,
+            Err(status) => return Reply(status, None, String::new()),
+        };
+
+        if errors.is_empty() {
+            self.open.retain(|open| *open != id);
+            self.committed.push(id);
+        }
+
+        Reply(200, None, results_body(results, errors))
+    }
+
+    fn rollback(&mut self, id: u64) -> Reply {
+        self.open.retain(|open| *open != id);
+        self.rolled_back.push(id);
+        Reply(200, None, results_body(Vec::new(), Vec::new()))
+    }
+
+    fn autocommit(&mut self, body: Option<&Json>) -> Reply {
+        match self.run(body, None) {
+            Ok((results, errors)) => Reply(200, None, results_body(results, errors)),
+            Err(status) => Reply(status, None, String::new()),
+        }
+    }
+
+    /// Runs the statements of the request, returning the results and errors, or the HTTP status
+    /// of a `MockResponse::http_error`
+    fn run(&mut self, body: Option<&Json>, transaction: Option<u64>)
+        -> Result<(Vec<Json>, Vec<Json>), u16>
+    {
+        let statements = body.and_then(|body| body.find("statements"))
+            .and_then(|statements| statements.as_array())
+            .cloned()
+            .unwrap_or(Vec::new());
+
+        let mut results = Vec::new();
+        let mut errors = Vec::new();
+
+        for statement in statements {
+            let text = statement.find("statement").and_then(|s| s.as_string()).unwrap_or("");
+            let include_stats = statement.find("includeStats")
+                .and_then(|include| include.as_boolean())
+                .unwrap_or(false);
+
+            // Statements without text only keep the transaction alive
+            if text.trim().is_empty() {
+                results.push(result_json(&[], &[], None));
+                continue;
+            }
+
+            self.statements.push(ReceivedStatement {
+                statement: text.to_owned(),
+                parameters: statement.find("parameters")
+                    .and_then(|params| params.as_object())
+                    .cloned()
+                    .unwrap_or(BTreeMap::new()),
+                transaction: transaction,
+            });
+
+            match self.response(text).kind {
+                ResponseKind::Result { columns, rows, stats } => {
+                    let stats = if include_stats { Some(stats) } else { None };
+                    results.push(result_json(&columns, &rows, stats));
+                },
+                ResponseKind::Error { code, message } => {
+                    let mut error = BTreeMap::new();
+                    error.insert("code".to_owned(), code.to_json());
+                    error.insert("message".to_owned(), message.to_json());
+                    errors.push(Json::Object(error));
+                    break;
+                },
+                ResponseKind::Http(status) => return Err(status),
+            }
+        }
+
+        // As in neo4j, errors roll back the transaction
+        if let Some(id) = transaction {
+            if !errors.is_empty() {
+                self.open.retain(|open| *open != id);
+                self.rolled_back.push(id);
+            }
+        }
+
+        Ok((results, errors))
+    }
+
+    fn response(&mut self, text: &str) -> MockResponse {
+        if let Some((_, response)) = self.rules.iter().find(|rule| text.contains(&rule.0[..])) {
+            return response.clone();
+        }
+
+        self.responses.pop_front().unwrap_or(MockResponse::empty())
+    }
+
+    fn transaction_body(&self, id: u64, results: Vec<Json>, errors: Vec<Json>) -> String {
+        let expires = time::now_utc() + time::Duration::seconds(60);
+
+        let mut transaction = BTreeMap::new();
+        transaction.insert("expires".to_owned(), expires.rfc822z().to_string().to_json());
+
+        let mut body = BTreeMap::new();
+        body.insert("commit".to_owned(),
+                    format!("{}/transaction/{}/commit", self.base_url, id).to_json());
+        body.insert("transaction".to_owned(), Json::Object(transaction));
+        body.insert("results".to_owned(), Json::Array(results));
+        body.insert("errors".to_owned(), Json::Array(errors));
+
+        Json::Object(body).to_string()
+    }
+}
+
+fn result_json(columns: &[String], rows: &[Vec<Json>], stats: Option<BTreeMap<String, Json>>)
+    -> Json
+{
+    let mut result = BTreeMap::new();
+    result.insert("columns".to_owned(), columns.to_json());
+    result.insert("data".to_owned(), Json::Array(rows.iter().map(|row| {
+        let mut data = BTreeMap::new();
+        data.insert("row".to_owned(), row.to_json());
+        Json::Object(data)
+    }).collect()));
+
+    if let Some(counters) = stats {
+        let mut stats = BTreeMap::new();
+        for name in &["nodes_created", "nodes_deleted", "properties_set", "relationships_created",
+                      "relationship_deleted", "labels_added", "labels_removed", "indexes_added",
+                      "indexes_removed", "constraints_added", "constraints_removed"] {
+            stats.insert((*name).to_owned(), 0u64.to_json());
+        }
+
+        let contains_updates = counters.values().any(|value| value.as_u64() != Some(0));
+        stats.extend(counters);
+        stats.insert("contains_updates".to_owned(), contains_updates.to_json());
+        result.insert("stats".to_owned(), Json::Object(stats));
+    }
+
+    Json::Object(result)
+}
+
+fn results_body(results: Vec<Json>, errors: Vec<Json>) -> String {
+    let mut body = BTreeMap::new();
+    body.insert("results".to_owned(), Json::Array(results));
+    body.insert("errors".to_owned(), Json::Array(errors));
+    Json::Object(body).to_string()
+}
+
+fn error_body(code: &str, message: &str) -> String {
+    let mut error = BTreeMap::new();
+    error.insert("code".to_owned(), code.to_json());
+    error.insert("message".to_owned(), message.to_json());
+    results_body(Vec::new(), vec![Json::Object(error)])
+}
+
+fn not_found(path: &str) -> Reply {
+    Reply(404, None, error_body("Neo.ClientError.Request.Invalid",
+                                &format!("No mock endpoint for {}", path)))
+}
+
+fn transaction_not_found(id: &str) -> Reply {
+    Reply(404, None, error_body("Neo.ClientError.Transaction.TransactionNotFound",
+                                &format!("Unrecognized transaction id {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use super::*;
+
+    #[test]
+    fn autocommit_with_responses() {
+        let server = MockServer::start().unwrap()
+            .with_version("3.1.0")
+            .with_response(MockResponse::rows(&["n"], vec![vec![Json::U64(1)], vec![Json::U64(2)]]));
+
+        let graph = server.connect().unwrap();
+        assert_eq!("3.1.0", graph.neo4j_version().to_string());
+
+        let rows: Vec<(u64,)> = graph.cypher().exec("RETURN 1".into()).unwrap();
+        assert_eq!(vec![(1,), (2,)], rows);
+
+        // The queue is empty, so the result is empty
+        let rows: Vec<(u64,)> = graph.cypher().exec("RETURN 2".into()).unwrap();
+        assert!(rows.is_empty());
+
+        server.assert_statement_count(2);
+        assert_eq!(None, server.statements()[0].transaction);
+        assert_eq!(Some("Basic bmVvNGo6bmVvNGo=".to_owned()), server.requests()[0].authorization);
+    }
+
+    #[test]
+    fn errors_and_params() {
+        let server = MockServer::start().unwrap()
+            .with_response_to("FAIL", MockResponse::error("Neo.ClientError.Statement.SyntaxError",
+                                                          "Invalid input"));
+        let graph = server.connect().unwrap();
+
+        let error = graph.cypher().exec::<()>("FAIL".into()).unwrap_err();
+        let errors = error.neo4j_errors().unwrap();
+        assert_eq!("Neo.ClientError.Statement.SyntaxError", errors[0].code);
+
+        let statement = ::Statement::new("MATCH (n {name: {name}}) RETURN n")
+            .with_param("name", "Rust".to_owned());
+        graph.cypher().exec::<()>(statement).unwrap();
+
+        server.assert_received_with_param("MATCH (n", "name", "Rust".to_owned());
+        server.assert_not_received("DELETE");
+    }
+
+    #[test]
+    fn http_errors() {
+        let server = MockServer::start().unwrap()
+            .with_response(MockResponse::http_error(503));
+        let graph = server.connect().unwrap();
+
+        assert!(graph.cypher().exec::<()>("RETURN 1".into()).is_err());
+    }
+
+    #[test]
+    fn transaction_lifecycle() {
+        let server = MockServer::start().unwrap();
+        let graph = server.connect().unwrap();
+
+        let (mut transaction, _) = graph.cypher().transaction()
+            .begin::<()>(Some("CREATE (n:A)".into()))
+            .unwrap();
+        transaction.exec::<()>("CREATE (n:B)".into()).unwrap();
+        assert_eq!(vec![1], server.open_transactions());
+        transaction.commit::<()>(None).unwrap();
+
+        let (transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+        transaction.rollback().unwrap();
+
+        server.respond_to("BAD", MockResponse::error("Neo.ClientError.Statement.SyntaxError", ""));
+        let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+        assert!(transaction.exec::<()>("BAD".into()).is_err());
+
+        assert!(server.open_transactions().is_empty());
+        assert_eq!(vec![1], server.committed_transactions());
+        assert_eq!(vec![2, 3], server.rolled_back_transactions());
+
+        let transactions: Vec<_> = server.statements().iter().map(|s| s.transaction).collect();
+        assert_eq!(vec![Some(1), Some(1), Some(3)], transactions);
+    }
+}
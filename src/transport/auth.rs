@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use hyper::header::Headers;
+
+use ::credentials::Credentials;
+use ::cypher::Statement;
+use ::error::GraphError;
+use super::{Transport, TransportRequest, TransportResponse};
+
+/// Adds the `Authorization` header of the credentials to each request
+///
+/// The header is built when a request is sent, so the credentials are not kept in the headers
+/// of `Cypher` and `Transaction`. `GraphClient::connect` and `connect_with_transport` wrap the
+/// transport in a `BasicAuthTransport` when the url holds credentials.
+pub struct BasicAuthTransport {
+    inner: Arc<dyn Transport>,
+    credentials: Credentials,
+}
+
+impl BasicAuthTransport {
+    pub fn new(inner: Arc<dyn Transport>, credentials: Credentials) -> Self {
+        BasicAuthTransport {
+            inner: inner,
+            credentials: credentials,
+        }
+    }
+
+    pub fn inner(&self) -> &Arc<dyn Transport> {
+        &self.inner
+    }
+
+    fn headers(&self, headers: &Headers) -> Headers {
+        let mut headers = headers.clone();
+        headers.set(self.credentials.authorization());
+        headers
+    }
+}
+
+impl Transport for BasicAuthTransport {
+    fn send(&self, request: &TransportRequest) -> Result<TransportResponse, GraphError> {
+        let mut request = request.clone();
+        request.headers.set(self.credentials.authorization());
+        self.inner.send(&request)
+    }
+
+    fn begin(&self, endpoint: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.inner.begin(endpoint, &self.headers(headers), statements)
+    }
+
+    fn exec(&self, transaction: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.inner.exec(transaction, &self.headers(headers), statements)
+    }
+
+    fn commit(&self, commit: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.inner.commit(commit, &self.headers(headers), statements)
+    }
+
+    fn rollback(&self, transaction: &str, headers: &Headers)
+        -> Result<TransportResponse, GraphError>
+    {
+        self.inner.rollback(transaction, &self.headers(headers))
+    }
+}
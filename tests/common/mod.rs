@@ -0,0 +1,27 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+#[cfg(feature = "derive")]
+use rusted_cypher::ogm::GraphNode;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(GraphNode))]
+#[cfg_attr(feature = "derive", label = "INTG_TEST_LANGUAGE")]
+pub struct Language {
+    #[cfg_attr(feature = "derive", key)]
+    pub name: String,
+    pub level: String,
+    pub safe: bool,
+}
+
+impl_decodable!(Language { name, level, safe });
+
+impl Language {
+    pub fn new(name: &str, level: &str, safe: bool) -> Self {
+        Language {
+            name: name.to_owned(),
+            level: level.to_owned(),
+            safe: safe,
+        }
+    }
+}
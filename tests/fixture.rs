@@ -0,0 +1,88 @@
+//! Runs without a server, on the in-memory graph of the `testing` feature:
+//! `cargo test --features testing --test fixture`
+#![cfg(feature = "testing")]
+
+extern crate rusted_cypher;
+
+use std::thread;
+use rusted_cypher::fixture::Fixture;
+use rusted_cypher::memory::MemoryGraph;
+
+const DATASET: &'static str = r#"{
+    "nodes": [
+        {"id": "rust", "labels": ["Language", "Compiled"], "properties": {"name": "Rust"}},
+        {"id": "ocaml", "labels": ["Language"], "properties": {"name": "OCaml"}},
+        {"id": 3, "labels": ["Language"], "properties": {"name": "C++"}}
+    ],
+    "relationships": [
+        {"start": "rust", "end": "ocaml", "type": "INFLUENCED_BY"},
+        {"start": "rust", "end": 3, "type": "INFLUENCED_BY", "properties": {"weight": 2}}
+    ]
+}"#;
+
+#[test]
+fn namespaces_are_isolated() {
+    let memory = MemoryGraph::new();
+    let first = Fixture::new(memory.connect().unwrap());
+    let second = Fixture::new(memory.connect().unwrap());
+
+    first.load_cypher("CREATE (:#{Language} {name: 'Rust'});\n\
+                       CREATE (:#{Language} {name: 'C'});").unwrap();
+    second.load_cypher("CREATE (:#{Language} {name: 'Go'})").unwrap();
+
+    let statement = first.statement("MATCH (n:#{Language}) RETURN n.name ORDER BY n.name").unwrap();
+    let names: Vec<(String,)> = first.graph().cypher().exec(statement).unwrap();
+    assert_eq!(vec![("C".to_owned(),), ("Rust".to_owned(),)], names);
+
+    first.teardown().unwrap();
+    assert_eq!(1, memory.node_count());
+
+    drop(second);
+    assert_eq!(0, memory.node_count());
+}
+
+#[test]
+fn json_dataset() {
+    let memory = MemoryGraph::new();
+    let fixture = Fixture::new(memory.connect().unwrap());
+
+    let ids = fixture.load_json(DATASET).unwrap();
+    assert_eq!(3, ids.len());
+    assert!(ids.contains_key("rust") && ids.contains_key("3"));
+
+    let statement = fixture.statement("MATCH (:#{Compiled})-[r:INFLUENCED_BY]->(n:#{Language}) \
+                                       RETURN n.name, r.weight ORDER BY n.name").unwrap();
+    let rows: Vec<(String, Option<u64>)> = fixture.graph().cypher().exec(statement).unwrap();
+    assert_eq!(vec![("C++".to_owned(), Some(2)), ("OCaml".to_owned(), None)], rows);
+
+    assert!(fixture.load_json(r#"{"nodes": [{"id": 1, "properties": {}}]}"#).is_err());
+    assert!(fixture.load_json(r#"{"relationships": [{"start": 1, "end": 2, "type": "A"}]}"#)
+        .is_err());
+
+    drop(fixture);
+    assert_eq!((0, 0), (memory.node_count(), memory.relationship_count()));
+}
+
+#[test]
+fn cleanup_after_panic() {
+    let memory = MemoryGraph::new();
+    let graph = memory.clone();
+
+    let result = thread::spawn(move || {
+        let fixture = Fixture::new(graph.connect().unwrap());
+        fixture.load_cypher("CREATE (:#{Language} {name: 'Rust'})").unwrap();
+        assert_eq!(0, graph.node_count(), "fails while the fixture holds a node");
+    }).join();
+
+    assert!(result.is_err());
+    assert_eq!(0, memory.node_count());
+}
+
+#[test]
+fn failed_dataset_is_rolled_back() {
+    let memory = MemoryGraph::new();
+    let fixture = Fixture::new(memory.connect().unwrap());
+
+    assert!(fixture.load_cypher("CREATE (:#{Language} {name: 'Rust'}); RETURN 1 / 0").is_err());
+    assert_eq!(0, memory.node_count());
+}
@@ -0,0 +1,149 @@
+//! Runs without a server, on the in-memory graph of the `testing` feature:
+//! `cargo test --features testing --test memory_graph`
+#![cfg(feature = "testing")]
+
+extern crate rustc_serialize;
+#[macro_use]
+extern crate rusted_cypher;
+
+mod common;
+
+use rusted_cypher::{GraphClient, Statement};
+use rusted_cypher::error::GraphError;
+use rusted_cypher::memory::MemoryGraph;
+
+use common::Language;
+
+/// A repository like the ones the in-memory graph is meant to test
+struct Languages<'a> {
+    graph: &'a GraphClient,
+}
+
+impl<'a> Languages<'a> {
+    fn add(&self, name: &str, level: &str, safe: bool) -> Result<(), GraphError> {
+        let statement = Statement::new("MERGE (n:LANGUAGE {name: {name}}) \
+                                        SET n.level = {level}, n.safe = {safe}")
+            .with_param("name", name.to_owned())
+            .with_param("level", level.to_owned())
+            .with_param("safe", safe);
+
+        self.graph.cypher().exec::<()>(statement)?;
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Result<Option<Language>, GraphError> {
+        let statement = Statement::new("MATCH (n:LANGUAGE) WHERE n.name = {name} RETURN n")
+            .with_param("name", name.to_owned());
+
+        let mut rows: Vec<(Language,)> = self.graph.cypher().exec(statement)?;
+        Ok(rows.pop().map(|row| row.0))
+    }
+
+    fn safe_names(&self) -> Result<Vec<String>, GraphError> {
+        let rows: Vec<(String,)> = self.graph.cypher().exec(
+            "MATCH (n:LANGUAGE {safe: true}) RETURN n.name AS name ORDER BY name".into())?;
+        Ok(rows.into_iter().map(|row| row.0).collect())
+    }
+
+    fn remove(&self, name: &str) -> Result<u64, GraphError> {
+        let statement = Statement::new("MATCH (n:LANGUAGE {name: {name}}) DETACH DELETE n")
+            .with_param("name", name.to_owned())
+            .with_stats();
+
+        let result = self.graph.cypher().exec_result::<()>(statement)?;
+        Ok(result.stats().map(|stats| stats.nodes_deleted).unwrap_or(0))
+    }
+}
+
+#[test]
+fn repository_without_server() {
+    let memory = MemoryGraph::new();
+    let graph = memory.connect().unwrap();
+    let languages = Languages { graph: &graph };
+
+    languages.add("Rust", "low", true).unwrap();
+    languages.add("C", "low", false).unwrap();
+    languages.add("Python", "high", true).unwrap();
+    languages.add("Rust", "high", true).unwrap();
+
+    assert_eq!(3, memory.node_count());
+    assert_eq!(Some(Language::new("Rust", "high", true)),
+               languages.find("Rust").unwrap());
+    assert_eq!(None, languages.find("Go").unwrap());
+    assert_eq!(vec!["Python".to_owned(), "Rust".to_owned()], languages.safe_names().unwrap());
+
+    assert_eq!(1, languages.remove("C").unwrap());
+    assert_eq!(0, languages.remove("C").unwrap());
+    assert_eq!(2, memory.node_count());
+}
+
+#[test]
+fn relationships_and_stats() {
+    let memory = MemoryGraph::new();
+    let graph = memory.connect().unwrap();
+
+    let statement = Statement::new("CREATE (r:LANGUAGE {name: 'Rust'})-[:INFLUENCED_BY]->\
+                                    (:LANGUAGE {name: 'OCaml'}), \
+                                    (r)-[:INFLUENCED_BY]->(:LANGUAGE {name: 'C++'})")
+        .with_stats();
+    let result = graph.cypher().exec_result::<()>(statement).unwrap();
+
+    let stats = result.stats().unwrap();
+    assert!(stats.contains_updates);
+    assert_eq!((3, 2, 3, 3), (stats.nodes_created, stats.relationships_created,
+                              stats.labels_added, stats.properties_set));
+
+    let rows: Vec<(String, Vec<String>)> = graph.cypher().exec(
+        "MATCH (n:LANGUAGE)-[:INFLUENCED_BY]->(m) \
+         RETURN n.name, collect(m.name) AS influences".into()).unwrap();
+    assert_eq!(1, rows.len());
+    assert_eq!("Rust", rows[0].0);
+    assert_eq!(2, rows[0].1.len());
+
+    let result = graph.cypher().exec_raw(
+        Statement::new("MATCH (n {name: 'Rust'})-[r]->(m {name: 'OCaml'}) RETURN n, r, m")
+            .with_graph()).unwrap();
+    let row_graph = result.rows()[0].graph.as_ref().unwrap();
+    assert_eq!(2, row_graph.nodes.len());
+    assert_eq!("INFLUENCED_BY", row_graph.relationships[0].rel_type);
+    assert_eq!(2, memory.relationship_count());
+}
+
+#[test]
+fn transactions() {
+    let memory = MemoryGraph::new();
+    let graph = memory.connect().unwrap();
+
+    let (mut transaction, _) = graph.cypher().transaction()
+        .begin::<()>(Some("CREATE (:TX {id: 1})".into()))
+        .unwrap();
+    let count: Vec<(u64,)> = transaction.exec("MATCH (n:TX) RETURN count(n)".into()).unwrap();
+    assert_eq!(vec![(1,)], count);
+    assert_eq!(0, memory.node_count());
+    transaction.commit::<()>(None).unwrap();
+    assert_eq!(1, memory.node_count());
+
+    let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.exec::<()>("MATCH (n:TX) DELETE n".into()).unwrap();
+    transaction.rollback().unwrap();
+    assert_eq!(1, memory.node_count());
+
+    // Changes are discarded when a statement fails
+    let (mut transaction, _) = graph.cypher().transaction()
+        .begin::<()>(Some("CREATE (:TX {id: 2})".into()))
+        .unwrap();
+    let error = transaction.exec::<()>("MATCH (n:TX) RETURN n.id / 0".into()).unwrap_err();
+    assert_eq!("Neo.ClientError.Statement.ArithmeticError", error.neo4j_errors().unwrap()[0].code);
+    assert!(memory.open_transactions().is_empty());
+    assert_eq!(1, memory.node_count());
+}
+
+#[test]
+fn unsupported_cypher() {
+    let graph = MemoryGraph::new().connect().unwrap();
+
+    let error = graph.cypher().exec::<()>("CREATE INDEX ON :LANGUAGE(name)".into()).unwrap_err();
+    let errors = error.neo4j_errors().unwrap();
+    assert_eq!("Neo.ClientError.Statement.SyntaxError", errors[0].code);
+    assert!(errors[0].message.contains("not supported"));
+}
@@ -0,0 +1,2 @@
+// Schema statements are run outside of the migration transaction
+CREATE CONSTRAINT ON (n:MIGRATION_TEST) ASSERT n.id IS UNIQUE;
@@ -0,0 +1,2 @@
+CREATE (:MIGRATION_TEST {id: 1, name: 'first; with a semicolon'});
+CREATE (:MIGRATION_TEST {id: 2, name: 'second'});
@@ -0,0 +1,216 @@
+//! Runs without a server, through the mock of the `testing` feature:
+//! `cargo test --features testing --test mock_server`
+#![cfg(feature = "testing")]
+
+extern crate rustc_serialize;
+#[macro_use]
+extern crate rusted_cypher;
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use rustc_serialize::json::{Json, ToJson};
+use rusted_cypher::Statement;
+use rusted_cypher::cypher::trace::{Span, SpanEnd, SpanStart, Tracer};
+use rusted_cypher::testing::{MockResponse, MockServer};
+
+use common::Language;
+
+fn language_json(name: &str, level: &str, safe: bool) -> Json {
+    Json::from_str(&format!(r#"{{"name": "{}", "level": "{}", "safe": {}}}"#, name, level, safe))
+        .unwrap()
+}
+
+#[test]
+fn decode_struct_rows() {
+    let server = MockServer::start().unwrap()
+        .with_response(MockResponse::rows(&["n"], vec![vec![language_json("Rust", "low", true)]]));
+    let graph = server.connect().unwrap();
+
+    let results: Vec<(Language,)> = graph.cypher()
+        .exec("MATCH (n:LANGUAGE) RETURN n".into())
+        .unwrap();
+
+    assert_eq!(vec![(Language::new("Rust", "low", true),)],
+               results);
+}
+
+#[test]
+fn stats_when_requested() {
+    let server = MockServer::start().unwrap()
+        .with_response(MockResponse::empty().with_stat("nodes_created", 2));
+    let graph = server.connect().unwrap();
+
+    let statement = Statement::new("CREATE (:A), (:A)").with_stats();
+    let result = graph.cypher().exec_result::<()>(statement).unwrap();
+
+    let stats = result.stats().unwrap();
+    assert!(stats.contains_updates);
+    assert_eq!(2, stats.nodes_created);
+}
+
+#[test]
+fn statements_in_transaction() {
+    let server = MockServer::start().unwrap();
+    let graph = server.connect().unwrap();
+
+    let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.exec::<()>(Statement::new("CREATE (n:TX {id: {id}})").with_param("id", 1))
+        .unwrap();
+    transaction.reset_timeout().unwrap();
+    transaction.commit::<()>(Some("CREATE (n:TX {id: 2})".into())).unwrap();
+
+    server.assert_statement_count(2);
+    server.assert_received_with_param("CREATE (n:TX", "id", 1);
+    assert_eq!(vec![1], server.committed_transactions());
+
+    let request = server.requests().into_iter()
+        .find(|request| request.path.ends_with("/commit"))
+        .unwrap();
+    assert_eq!("POST", request.method);
+    assert_eq!(Some("CREATE (n:TX {id: 2})".to_json()),
+               request.body.as_ref().and_then(|body| body.find_path(&["statements"]))
+                   .and_then(|statements| statements.as_array())
+                   .and_then(|statements| statements[0].find("statement"))
+                   .cloned());
+}
+
+#[test]
+fn failed_statement_rolls_back() {
+    let server = MockServer::start().unwrap()
+        .with_response(MockResponse::empty())
+        .with_response(MockResponse::error("Neo.ClientError.Schema.ConstraintValidationFailed",
+                                           "Node already exists"));
+    let graph = server.connect().unwrap();
+
+    let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.exec::<()>("CREATE (:A {id: 1})".into()).unwrap();
+
+    let error = transaction.exec::<()>("CREATE (:A {id: 1})".into()).unwrap_err();
+    assert_eq!("Node already exists", error.neo4j_errors().unwrap()[0].message);
+
+    assert!(server.open_transactions().is_empty());
+    assert_eq!(vec![1], server.rolled_back_transactions());
+    assert!(transaction.commit::<()>(None).is_err());
+}
+
+#[test]
+fn transaction_metadata_and_timeout() {
+    let server = MockServer::start().unwrap().with_version("3.4.0");
+    let graph = server.connect().unwrap();
+
+    let (transaction, _) = graph.cypher().transaction()
+        .with_metadata("service", "billing".to_owned())
+        .with_timeout(Duration::from_millis(1500))
+        .begin::<()>(Some("CREATE (:TX)".into()))
+        .unwrap();
+    transaction.rollback().unwrap();
+
+    let begin = server.requests().into_iter()
+        .find(|request| request.method == "POST" && request.path.ends_with("/transaction"))
+        .unwrap();
+    assert_eq!(Some("1500".to_owned()), begin.max_execution_time);
+
+    let statements = server.statements();
+    assert_eq!("CALL dbms.setTXMetaData({metadata})", statements[0].statement);
+    assert_eq!(Some(&Json::from_str(r#"{"service": "billing"}"#).unwrap()),
+               statements[0].parameters.get("metadata"));
+    assert_eq!("CREATE (:TX)", statements[1].statement);
+    assert_eq!(Some(1), statements[1].transaction);
+}
+
+#[test]
+fn transaction_metadata_requires_neo4j_3_4() {
+    let server = MockServer::start().unwrap().with_version("3.3.9");
+    let graph = server.connect().unwrap();
+    server.clear_received();
+
+    let error = graph.cypher().transaction()
+        .with_metadata("service", "billing".to_owned())
+        .begin::<()>(None)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("neo4j 3.4"));
+    assert!(server.requests().is_empty());
+
+    let (transaction, _) = graph.cypher().transaction()
+        .with_timeout(Duration::from_secs(1))
+        .begin::<()>(None)
+        .unwrap();
+    transaction.rollback().unwrap();
+    assert!(server.statements().is_empty());
+}
+
+/// Propagates a trace context, without recording spans
+struct ContextTracer;
+
+struct NoSpan;
+
+impl Span for NoSpan {
+    fn finish(&mut self, _: &SpanEnd) {}
+}
+
+impl Tracer for ContextTracer {
+    fn start(&self, _: &SpanStart) -> Box<dyn Span> {
+        Box::new(NoSpan)
+    }
+
+    fn context(&self) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        context.insert("traceparent".to_owned(),
+                       "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_owned());
+        context
+    }
+}
+
+#[test]
+fn trace_context_skipped_before_neo4j_3_4() {
+    let server = MockServer::start().unwrap().with_version("3.0.12");
+    let graph = server.connect().unwrap().with_tracer(Arc::new(ContextTracer));
+    server.clear_received();
+
+    let (transaction, _) = graph.cypher().transaction()
+        .begin::<()>(Some("CREATE (:TX)".into()))
+        .unwrap();
+    assert!(transaction.metadata().is_empty());
+    transaction.rollback().unwrap();
+
+    let statements = server.statements();
+    assert_eq!(1, statements.len());
+    assert_eq!("CREATE (:TX)", statements[0].statement);
+
+    let error = graph.cypher().transaction()
+        .with_metadata("service", "billing".to_owned())
+        .begin::<()>(None)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("neo4j 3.4"));
+}
+
+#[test]
+fn transaction_timeout_rounds_up_to_milliseconds() {
+    let server = MockServer::start().unwrap();
+    let graph = server.connect().unwrap();
+
+    let (transaction, _) = graph.cypher().transaction()
+        .with_timeout(Duration::from_micros(500))
+        .begin::<()>(None)
+        .unwrap();
+    transaction.rollback().unwrap();
+
+    let begin = server.requests().into_iter()
+        .find(|request| request.method == "POST" && request.path.ends_with("/transaction"))
+        .unwrap();
+    assert_eq!(Some("1".to_owned()), begin.max_execution_time);
+
+    server.clear_received();
+    let error = graph.cypher().transaction()
+        .with_timeout(Duration::from_secs(0))
+        .begin::<()>(None)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("greater than zero"));
+    assert!(server.requests().is_empty());
+}
@@ -0,0 +1,111 @@
+//! Runs without a server, on the in-memory graph of the `testing` feature:
+//! `cargo test --features testing --test observer`
+#![cfg(feature = "testing")]
+
+extern crate rusted_cypher;
+
+use std::sync::{Arc, Mutex};
+use rusted_cypher::cypher::observer::{Event, Observer};
+use rusted_cypher::memory::MemoryGraph;
+
+/// Records a short description of each event
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        self.events.lock().unwrap().drain(..).collect()
+    }
+}
+
+impl Observer for Recorder {
+    fn on_event(&self, event: &Event) {
+        let description = match *event {
+            Event::StatementStarted { statement } => format!("started {}", statement.statement()),
+            Event::StatementFinished { succeeded, rows, error, .. } => {
+                format!("finished {} {:?} {:?}", succeeded, rows, error)
+            },
+            Event::BytesTransferred { sent, received } => {
+                assert!(received > 0);
+                format!("bytes {}", sent > 0)
+            },
+            Event::TransactionBegun => "begun".to_owned(),
+            Event::TransactionCommitted { .. } => "committed".to_owned(),
+            Event::TransactionRolledBack { .. } => "rolled back".to_owned(),
+            Event::TransactionExpired => "expired".to_owned(),
+            Event::Error { code } => format!("error {}", code),
+        };
+
+        self.events.lock().unwrap().push(description);
+    }
+}
+
+#[test]
+fn statements_and_errors() {
+    let recorder = Arc::new(Recorder::default());
+    let graph = MemoryGraph::new().connect().unwrap().with_observer(recorder.clone());
+
+    graph.cypher().exec_raw("UNWIND [1, 2, 3] AS x RETURN x".into()).unwrap();
+    assert_eq!(vec!["started UNWIND [1, 2, 3] AS x RETURN x",
+                    "bytes true",
+                    "finished true Some(3) None"],
+               recorder.take());
+
+    graph.cypher().exec_raw("RETURN 1 / 0".into()).unwrap_err();
+    assert_eq!(vec!["started RETURN 1 / 0",
+                    "bytes true",
+                    "finished false None Some(\"Neo.ClientError.Statement.ArithmeticError\")",
+                    "error Neo.ClientError.Statement.ArithmeticError"],
+               recorder.take());
+}
+
+#[test]
+fn transaction_lifecycle() {
+    let recorder = Arc::new(Recorder::default());
+    let memory = MemoryGraph::new();
+    let mut graph = memory.connect().unwrap();
+    graph.add_observer(recorder.clone());
+
+    let (mut transaction, _) = graph.cypher().transaction()
+        .with_metadata("service", "billing".to_owned())
+        .begin::<()>(Some("CREATE (:TX)".into()))
+        .unwrap();
+    transaction.exec_raw("MATCH (n:TX) RETURN n".into()).unwrap();
+    transaction.commit::<()>(None).unwrap();
+
+    assert_eq!(vec!["started CALL dbms.setTXMetaData({metadata})",
+                    "started CREATE (:TX)",
+                    "bytes true",
+                    "finished true Some(0) None",
+                    "finished true Some(0) None",
+                    "begun",
+                    "started MATCH (n:TX) RETURN n",
+                    "bytes true",
+                    "finished true Some(1) None",
+                    "bytes false",
+                    "committed"],
+               recorder.take());
+
+    let (transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.rollback().unwrap();
+    assert_eq!(vec!["bytes false", "begun", "bytes false", "rolled back"], recorder.take());
+}
+
+#[test]
+fn expired_transaction() {
+    let recorder = Arc::new(Recorder::default());
+    let graph = MemoryGraph::new().connect().unwrap().with_observer(recorder.clone());
+
+    // The in-memory graph discards the transaction when a statement fails
+    let (mut transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.exec::<()>("RETURN 1 / 0".into()).unwrap_err();
+    recorder.take();
+
+    transaction.commit::<()>(None).unwrap_err();
+    assert_eq!(vec!["bytes false",
+                    "error Neo.ClientError.Transaction.TransactionNotFound",
+                    "expired"],
+               recorder.take());
+}
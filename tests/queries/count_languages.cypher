@@ -0,0 +1 @@
+MATCH (n:INTG_TEST_REGISTRY) RETURN count(n);
@@ -0,0 +1,10 @@
+// name: create_language
+// params: name, level, safe
+CREATE (n:INTG_TEST_REGISTRY {name: {name}, level: {level}, safe: {safe}});
+
+// name: find_language
+// params: name
+MATCH (n:INTG_TEST_REGISTRY) WHERE n.name = {name} RETURN n.level, n.safe;
+
+// name: delete_languages
+MATCH (n:INTG_TEST_REGISTRY) DELETE n;
@@ -0,0 +1,102 @@
+//! Runs without a server, on the in-memory graph of the `testing` feature:
+//! `cargo test --features testing --test trace`
+#![cfg(feature = "testing")]
+
+extern crate rustc_serialize;
+extern crate rusted_cypher;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use rustc_serialize::json::ToJson;
+use rusted_cypher::cypher::trace::{Span, SpanEnd, SpanStart, Tracer};
+use rusted_cypher::memory::MemoryGraph;
+
+const TRACEPARENT: &'static str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+/// Records a line for each span finished
+#[derive(Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<String>>>,
+}
+
+struct RecordedSpan {
+    start: String,
+    spans: Arc<Mutex<Vec<String>>>,
+}
+
+impl Span for RecordedSpan {
+    fn finish(&mut self, end: &SpanEnd) {
+        let line = format!("{} succeeded={} rows={} error={:?}",
+                           self.start, end.succeeded, end.rows, end.error);
+        assert!(end.bytes > 0);
+        self.spans.lock().unwrap().push(line);
+    }
+}
+
+impl Tracer for Recorder {
+    fn start(&self, span: &SpanStart) -> Box<dyn Span> {
+        Box::new(RecordedSpan {
+            start: format!("{} {} {:?} {:?}", span.operation, span.server, span.database,
+                           span.summary),
+            spans: self.spans.clone(),
+        })
+    }
+
+    fn context(&self) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        context.insert("traceparent".to_owned(), TRACEPARENT.to_owned());
+        context.insert("service".to_owned(), "ignored".to_owned());
+        context
+    }
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        self.spans.lock().unwrap().drain(..).collect()
+    }
+}
+
+#[test]
+fn spans_of_cypher_calls() {
+    let tracer = Arc::new(Recorder::default());
+    let graph = MemoryGraph::new().connect().unwrap().with_tracer(tracer.clone());
+
+    graph.cypher().exec_raw("UNWIND [1, 2] AS x\n  RETURN x".into()).unwrap();
+    graph.cypher().exec::<()>("RETURN 1 / {zero}".into()).unwrap_err();
+
+    assert_eq!(vec!["Cypher.exec memory:80 None \"UNWIND [1, 2] AS x RETURN x\" \
+                     succeeded=true rows=2 error=None",
+                    "Cypher.exec memory:80 None \"RETURN 1 / {zero}\" \
+                     succeeded=false rows=0 \
+                     error=Some(\"Neo.ClientError.Statement.ParameterMissing\")"],
+               tracer.take());
+}
+
+#[test]
+fn transaction_spans_and_context() {
+    let tracer = Arc::new(Recorder::default());
+    let graph = MemoryGraph::new().connect().unwrap().with_tracer(tracer.clone());
+
+    let (mut transaction, _) = graph.cypher().transaction()
+        .with_metadata("service", "billing".to_owned())
+        .begin::<()>(None)
+        .unwrap();
+
+    assert_eq!(Some(&TRACEPARENT.to_json()), transaction.metadata().get("traceparent"));
+    assert_eq!(Some(&"billing".to_json()), transaction.metadata().get("service"));
+
+    transaction.exec_raw("CREATE (n:TX) RETURN n".into()).unwrap();
+    transaction.commit::<()>(None).unwrap();
+
+    let spans = tracer.take();
+    assert_eq!(3, spans.len());
+    assert!(spans[0].starts_with("Transaction.begin memory:80 None \
+                                  \"CALL dbms.setTXMetaData({metadata})\" succeeded=true"));
+    assert!(spans[1].starts_with("Transaction.exec "));
+    assert!(spans[1].ends_with(" rows=1 error=None"));
+    assert!(spans[2].starts_with("Transaction.commit memory:80 None \"\" succeeded=true"));
+
+    let (transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.rollback().unwrap();
+    assert!(tracer.take()[1].starts_with("Transaction.rollback "));
+}
@@ -0,0 +1,94 @@
+//! Runs without a server, through the mock of the `testing` feature:
+//! `cargo test --features testing --test transport`
+#![cfg(feature = "testing")]
+
+extern crate hyper;
+extern crate rusted_cypher;
+
+use std::sync::{Arc, Mutex};
+use hyper::header::Headers;
+use rusted_cypher::{GraphClient, Statement};
+use rusted_cypher::error::GraphError;
+use rusted_cypher::testing::MockServer;
+use rusted_cypher::transport::{HttpTransport, Transport, TransportRequest, TransportResponse};
+
+/// Wraps another transport, keeping the operations and statements it receives
+struct OperationLog {
+    inner: HttpTransport,
+    operations: Mutex<Vec<String>>,
+}
+
+impl OperationLog {
+    fn log(&self, operation: &str, statements: &[Statement]) {
+        let statements: Vec<&str> = statements.iter().map(|s| s.statement()).collect();
+        self.operations.lock().unwrap().push(format!("{} {:?}", operation, statements));
+    }
+}
+
+impl Transport for OperationLog {
+    fn send(&self, request: &TransportRequest) -> Result<TransportResponse, GraphError> {
+        self.inner.send(request)
+    }
+
+    fn begin(&self, endpoint: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.log("begin", statements);
+        self.send_statements(endpoint, headers, statements)
+    }
+
+    fn exec(&self, transaction: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.log("exec", statements);
+        self.send_statements(transaction, headers, statements)
+    }
+
+    fn commit(&self, commit: &str, headers: &Headers, statements: &[Statement])
+        -> Result<TransportResponse, GraphError>
+    {
+        self.log("commit", statements);
+        self.send_statements(commit, headers, statements)
+    }
+
+    fn rollback(&self, transaction: &str, headers: &Headers)
+        -> Result<TransportResponse, GraphError>
+    {
+        self.log("rollback", &[]);
+        self.send(&TransportRequest::new(hyper::method::Method::Delete, transaction, headers, ""))
+    }
+}
+
+#[test]
+fn wrapped_transport_sees_every_operation() {
+    let server = MockServer::start().unwrap();
+    let transport = Arc::new(OperationLog {
+        inner: HttpTransport::new(),
+        operations: Mutex::new(Vec::new()),
+    });
+    let graph = GraphClient::connect_with_transport(&server.url(), transport.clone()).unwrap();
+
+    graph.cypher().exec::<()>("CREATE (:A)".into()).unwrap();
+
+    let (mut transaction, _) = graph.cypher().transaction()
+        .begin::<()>(Some("CREATE (:B)".into()))
+        .unwrap();
+    transaction.exec::<()>("CREATE (:C)".into()).unwrap();
+    transaction.commit::<()>(None).unwrap();
+
+    let (transaction, _) = graph.cypher().transaction().begin::<()>(None).unwrap();
+    transaction.rollback().unwrap();
+
+    assert_eq!(vec![
+        r#"commit ["CREATE (:A)"]"#.to_owned(),
+        r#"begin ["CREATE (:B)"]"#.to_owned(),
+        r#"exec ["CREATE (:C)"]"#.to_owned(),
+        r#"commit []"#.to_owned(),
+        r#"begin []"#.to_owned(),
+        r#"rollback []"#.to_owned(),
+    ], *transport.operations.lock().unwrap());
+
+    server.assert_statement_count(3);
+    assert_eq!(1, server.committed_transactions().len());
+    assert_eq!(1, server.rolled_back_transactions().len());
+}